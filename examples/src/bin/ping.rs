@@ -20,6 +20,9 @@ async fn main(peripherals: pins::ButtonPeripherals) {
     .await
     .unwrap();
 
+    // Like the dect_shell ping defaults
+    let rx_params = hophop::nrfxlib_phy::RxParams::new(dect.carrier(1665).unwrap(), 0x12345678);
+
     let transmitter_id = &ariel_os::identity::interface_eui48(0).unwrap();
     let transmitter_id_short = u16::from_be_bytes(transmitter_id.0[..2].try_into().unwrap());
     let transmitter_id_long = u32::from_be_bytes(transmitter_id.0[2..].try_into().unwrap());
@@ -35,7 +38,7 @@ async fn main(peripherals: pins::ButtonPeripherals) {
         // moment we can just leave it at that.
         if button0.is_high() {
             let received = dect
-                .rx()
+                .rx(rx_params)
                 .await
                 .expect("Receive operation failed as a whole");
 
@@ -75,29 +78,28 @@ async fn main(peripherals: pins::ButtonPeripherals) {
         }
 
         if button0.is_low() {
-            #[rustfmt::skip]
-            let mut pcc = [
-                // header format 000, 2 subslots
-                0x02,
-                // short networkID
-                0x41,
-                // Transmitter identity, later overwritten
-                0x12, 0x34,
-                // Transmit power and DF MCS as in what we've seen from dect_shell beacons
-                0x70,
-            ];
-            // Overwrite short transmitter ID
-            pcc[2..4].copy_from_slice(&transmitter_id_short.to_be_bytes());
+            // 3 subslots (encoded as 0x02 in the length field); transmit power and DF MCS as in
+            // what we've seen from dect_shell beacons.
+            let pcc = utils::pcc::Type1Builder::new(0x41, transmitter_id_short)
+                .packet_length(utils::pcc::PacketLengthType::Subslots, 3)
+                .unwrap()
+                .transmit_power(7)
+                .unwrap()
+                .build();
+            // The MAC PDU is padded up to the transport block size the PCC implies.
+            let parsed_pcc = utils::pcc::Pcc::parse(&pcc).unwrap();
             let mut pdc_buf = heapless::Vec::<u8, 256>::new();
-            // version 0, no security; beacon.
-            pdc_buf.push(0x01).unwrap();
-            // beacon details:
-            // full network ID
-            pdc_buf.extend_from_slice(&[0x41, 0x41, 0x41]).unwrap();
-            // full sender ID
-            pdc_buf
-                .extend_from_slice(&transmitter_id_long.to_be_bytes())
-                .unwrap();
+            let mut pdu = utils::mac_pdu::builder::MacPduBuilder::for_packet(
+                &mut pdc_buf,
+                &utils::transport_block::PdcFormat::from_pcc(&parsed_pcc).unwrap(),
+                utils::transport_block::PacketLength::from_pcc(&parsed_pcc),
+            );
+            pdu.header(&utils::mac_pdu::OwnedHeader::new(
+                utils::mac_pdu::OwnedMacCommonHeader::Beacon(
+                    utils::mac_pdu::OwnedBeacon::new(0x414141, transmitter_id_long).unwrap(),
+                ),
+            ))
+            .unwrap();
 
             // Clock starts ticking for building the message…
             let now = dect.time_get().await.unwrap();
@@ -110,39 +112,20 @@ async fn main(peripherals: pins::ButtonPeripherals) {
 
             // Our convention is that we transmit on data channel 1 -- setting that up or
             // multiplexing is TBD.
-            utils::mac_ie::InformationElement::new_6bit_with_length(
-                numbers::mac_ie::ie6bit::USER_PLANE_DATA_FLOW_1,
-                &userdata,
+            pdu.information_element(
+                &utils::mac_ie::InformationElement::new_6bit_with_length(
+                    numbers::mac_ie::ie6bit::USER_PLANE_DATA_FLOW_1,
+                    &userdata,
+                )
+                .unwrap(),
             )
-            .unwrap()
-            .serialize(&mut pdc_buf)
             .unwrap();
+            pdu.finish().unwrap();
 
-            // For the time being we accept that the PCC (which is copy-pasted from beacon
-            // messages) has a 50 byte payload, and we pad accordingly:
-            //
-            // ... or we just send in a subslot and then it's 17 length (1 subslot) or 33 (2
-            // subslots)
-            const LEN: usize = 33;
-
-            assert!(pdc_buf.len() < LEN);
-            while pdc_buf.len() < LEN {
-                // Single paddings are easier than calculating back whether the rest fits in a u8
-                // or u16 padding; on the long run, we'll have a "pad it up" function, or just emit
-                // an indefinite-length padding, as that (juding from Wireshark) auto-fills all the
-                // rest.
-                utils::mac_ie::InformationElement::new_5bit(
-                    numbers::mac_ie::ie5bit_len0::PADDING,
-                    &[],
-                )
-                .unwrap()
-                .serialize(&mut pdc_buf)
-                .unwrap();
-            }
-
-            dect.tx(transmit_time, 1665, 0x12345678, &pcc, &pdc_buf)
-                .await
-                .unwrap();
+            let tx_params =
+                hophop::nrfxlib_phy::TxParams::new(dect.carrier(1665).unwrap(), 0x12345678)
+                    .start_time(transmit_time);
+            dect.tx(tx_params, &pcc, &pdc_buf).await.unwrap();
 
             info!("Sent {} bytes PDC data", pdc_buf.len());
         }
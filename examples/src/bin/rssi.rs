@@ -5,6 +5,7 @@
 
 use ariel_os::debug::{ExitCode, exit, log::info};
 use ariel_os::time::Timer;
+use ts_103_636_numbers::band::BAND_1;
 
 #[ariel_os::task(autostart)]
 async fn main() {
@@ -17,10 +18,16 @@ async fn main() {
     for _ in 0..60 {
         info!("DECT time is {:?}", dect.time_get().await);
 
-        info!("Scanning band 1");
-        for carrier in 1657..=1677 {
+        info!("Scanning band {}", BAND_1.number());
+        for carrier in BAND_1.carriers() {
+            let carrier = dect.carrier(carrier).unwrap();
             if let Ok(rssi) = dect.rssi(carrier).await {
-                info!("RSSI for {} at {}: {:?}", carrier, rssi.0, rssi.1.data());
+                info!(
+                    "RSSI for {} at {}: {:?}",
+                    carrier.get(),
+                    rssi.0,
+                    rssi.1.data()
+                );
             }
         }
     }
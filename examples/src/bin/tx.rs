@@ -27,21 +27,25 @@ async fn blinky(peripherals: pins::ButtonPeripherals) {
         while button0.is_high() {}
         info!("Press.");
 
-        dect.tx(
-            0,
-            1665,
-            // FIXME: Not using a proper network ID yet
-            0x12345678,
-            // Beacon as seen by the dect_shell
-            &[17, 120, 150, 24, 112],
-            &[
-                1, 18, 52, 86, 0, 0, 0, 38, 73, 5, 176, 16, 6, 0, 13, 83, 7, 8, 12, 138, 160, 215,
-                2, 100, 64, 24, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0,
-            ],
-        )
-        .await
-        .unwrap();
+        // FIXME: Not using a proper network ID yet
+        let params = hophop::nrfxlib_phy::TxParams::new(dect.carrier(1665).unwrap(), 0x12345678)
+            .lbt(hophop::nrfxlib_phy::Lbt::subslots(2, -85).unwrap());
+        let result = dect
+            .tx(
+                params,
+                // Beacon as seen by the dect_shell
+                &[17, 120, 150, 24, 112],
+                &[
+                    1, 18, 52, 86, 0, 0, 0, 38, 73, 5, 176, 16, 6, 0, 13, 83, 7, 8, 12, 138, 160,
+                    215, 2, 100, 64, 24, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    0, 0, 0, 0, 0,
+                ],
+            )
+            .await;
+        match result {
+            Err(hophop::nrfxlib_phy::MixedError::ChannelBusy) => info!("Channel busy, not sent."),
+            result => result.unwrap(),
+        }
 
         // Debounce and wait for release
         Timer::after_millis(5).await;
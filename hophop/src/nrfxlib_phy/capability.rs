@@ -0,0 +1,63 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Capabilities of the modem as advertised to peers.
+
+use nrf_modem::nrfxlib_sys;
+use ts_103_636_utils::mac_ie::capability::{OperatingModes, PhyCapability, RdCapabilityBuilder};
+
+use super::DectPhy;
+
+/// What the modem reported in its capability event, in the coding of the RD Capability IE.
+#[derive(Debug, Copy, Clone)]
+pub(super) struct ModemCapability {
+    release: u8,
+    phy: PhyCapability,
+}
+
+impl ModemCapability {
+    /// Picks the capabilities at µ = 1 and β = 1 from the modem's report.
+    ///
+    /// The fields are taken over as libmodem reports them, which is in the coding of Table
+    /// 6.4.3.5-1 of ETSI TS 103 636-4. This returns `None` if there is no entry for µ = 1 and
+    /// β = 1 (coded as 0), or if a value does not fit its field.
+    pub(super) fn from_nrf(
+        capability: &nrfxlib_sys::nrf_modem_dect_phy_capability,
+    ) -> Option<Self> {
+        // SAFETY: libmodem provides as many entries as it counts.
+        let variants = unsafe {
+            capability
+                .variant
+                .as_slice(usize::from(capability.variant_count))
+        };
+        let base = variants
+            .iter()
+            .find(|variant| variant.mu == 0 && variant.beta == 0)?;
+        Some(Self {
+            release: capability.dect_version,
+            phy: PhyCapability {
+                rd_power_class: base.power_class,
+                max_nss_rx: base.rx_spatial_streams,
+                rx_for_tx_diversity: base.rx_tx_diversity,
+                rx_gain: base.rx_gain,
+                max_mcs: base.mcs_max,
+                soft_buffer_size: u8::try_from(base.harq_soft_buf_size).ok()?,
+                harq_processes: base.harq_process_count_max,
+                harq_feedback_delay: base.harq_feedback_delay,
+            },
+        })
+    }
+}
+
+impl DectPhy {
+    /// An RD Capability builder filled with what the modem reported at initialization.
+    ///
+    /// The device can operate as PT or FT, but higher-layer features (MAC security, mesh, paging,
+    /// …) are not advertised, because they depend on the application; set them on the builder as
+    /// needed.
+    ///
+    /// This returns `None` if the modem's report could not be expressed in the IE.
+    pub fn rd_capability(&self) -> Option<RdCapabilityBuilder> {
+        let capability = self.capability?;
+        RdCapabilityBuilder::new(capability.release, OperatingModes::PtAndFt, capability.phy).ok()
+    }
+}
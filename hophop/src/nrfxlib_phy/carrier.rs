@@ -0,0 +1,47 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Carriers that were checked to be usable with the modem's configuration.
+
+use ts_103_636_numbers::band::{BAND_1, BAND_2, BAND_4, BAND_9, BAND_22, Band};
+
+use super::{DectPhy, MixedError};
+
+/// Bands the nRF9151 is assumed to support.
+///
+/// FIXME: This list is not verified against the modem. Query the modem's supported bands at
+/// initialization, and check carriers against those instead.
+pub const NRF9151_BANDS: [Band; 5] = [BAND_1, BAND_2, BAND_4, BAND_9, BAND_22];
+
+/// Band group the PHY is configured for.
+///
+/// This is the group of the 1.9 GHz bands; the sub-GHz band 4 would need a different
+/// configuration.
+pub(super) const BAND_GROUP_INDEX: u8 = BAND_1.band_group_index();
+
+/// A carrier number that is in a band the modem supports in its configured band group.
+///
+/// Obtain one through [`DectPhy::carrier()`]. The carrier is only checked against the bands;
+/// regulatory [restrictions][ts_103_636_numbers::band::Restriction] are up to the application.
+#[derive(Debug, defmt::Format, Copy, Clone, PartialEq, Eq)]
+pub struct Carrier(u16);
+
+impl Carrier {
+    /// The carrier number as used in TS 103 636-2.
+    pub fn get(self) -> u16 {
+        self.0
+    }
+}
+
+impl DectPhy {
+    /// Checks that a carrier can be used with the modem's current configuration.
+    ///
+    /// Checking this up front avoids an operation that libmodem would reject with an error event
+    /// that can not be told apart from other failures.
+    pub fn carrier(&self, carrier: u16) -> Result<Carrier, MixedError> {
+        NRF9151_BANDS
+            .iter()
+            .any(|band| band.band_group_index() == BAND_GROUP_INDEX && band.contains(carrier))
+            .then_some(Carrier(carrier))
+            .ok_or(MixedError::UnsupportedCarrier(carrier))
+    }
+}
@@ -32,6 +32,12 @@ pub enum MixedError {
     General(Error),
     Phy(PhyErr),
     UsageError,
+    /// The carrier is not in any band the modem supports in its configured band group.
+    UnsupportedCarrier(u16),
+    /// Listen-before-talk found the channel busy, so nothing was sent.
+    ChannelBusy,
+    /// The operation completed successfully, but without reporting the expected result.
+    NoResult,
 }
 
 impl From<Error> for MixedError {
@@ -42,6 +48,11 @@ impl From<Error> for MixedError {
 
 impl From<PhyErr> for MixedError {
     fn from(input: PhyErr) -> Self {
+        if u32::from(input.0.get())
+            == nrfxlib_sys::nrf_modem_dect_phy_err_NRF_MODEM_DECT_PHY_ERR_LBT_CHANNEL_BUSY as u32
+        {
+            return MixedError::ChannelBusy;
+        }
         MixedError::Phy(input)
     }
 }
@@ -2,31 +2,88 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 //! High-level wrappers around the DECT PHY.
 
+use core::sync::atomic::{AtomicU32, Ordering};
+
 use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
 use nrf_modem::{Error, ErrorSource, nrfxlib_sys};
 
+mod capability;
+
+mod carrier;
+pub use carrier::{Carrier, NRF9151_BANDS};
+
 mod error;
-use error::{MixedError, PhyResult, PhyResultExt as _};
+pub use error::MixedError;
+use error::{PhyResult, PhyResultExt as _};
 
 mod latency;
 
+mod ring;
 mod rssi;
 mod rx;
+mod tx;
+pub use rx::{HeaderStatus, RecvResult, RxMetadata, RxMode, RxParams, RxStream};
+pub use tx::{Harq, Lbt, TxParams};
+
+/// Number of packets whose events can queue up in [`DECT_EVENTS`].
+///
+/// During continuous reception, the application may still process a packet while the next ones
+/// arrive. [`RX_RING`] holds about 15 packets of the 540 bytes that a PDC at the nRF9151's maximum
+/// MCS takes; events of further packets would only report that their data did not fit.
+const QUEUED_PACKETS: usize = 15;
+
+/// Slots in [`DECT_EVENTS`] that only [`DectEvent::Completed`] may take.
+///
+/// Completions are not dropped while there is space: the operation waiting for them would hang.
+/// Only one operation is active at a time, and [`discard_stale_events()`] empties the queue
+/// before one starts, but completions of abandoned operations may still arrive during it.
+const COMPLETION_RESERVE: usize = 3;
+
+// FIXME: Do we need the CS mutex?
+static DECT_EVENTS: embassy_sync::channel::Channel<
+    CriticalSectionRawMutex,
+    DectEventOuter,
+    { 2 * QUEUED_PACKETS + COMPLETION_RESERVE },
+> = embassy_sync::channel::Channel::new();
+
+/// Number of packets whose PCC event was dropped because [`DECT_EVENTS`] was full.
+///
+/// This is taken by [`RxStream::next_reception()`] and reported in the next [`RecvResult`].
+static LOST_PACKETS: AtomicU32 = AtomicU32::new(0);
 
-// FIXME: What's a good length? Probably events can pile up, like "here's the last data and by the
-// way the transaction is now complete". And do we need the CS mutex?
-static DECT_EVENTS: embassy_sync::channel::Channel<CriticalSectionRawMutex, DectEventOuter, 4> =
-    embassy_sync::channel::Channel::new();
+/// Source of the handles under which operations are started.
+///
+/// Every operation gets a new handle, so that late events of an abandoned operation (e.g. a
+/// dropped [`RxStream`]) can be told apart from those of the current one.
+static NEXT_HANDLE: AtomicU32 = AtomicU32::new(1);
+
+/// Picks a handle for a new operation.
+fn new_handle() -> u32 {
+    NEXT_HANDLE.fetch_add(1, Ordering::Relaxed)
+}
 
-/// Kind of a bump allocator for data that doesn't fit in the events.
+/// Discards all queued events before an operation is started.
 ///
-/// Might later be turned into a ring buffer if any methods support stream-processing multiple
-/// events.
+/// Operations are exclusive, so anything still queued belongs to operations that were abandoned,
+/// and would otherwise take up space until the next [`receive_event()`].
+fn discard_stale_events() {
+    while let Ok(outer) = DECT_EVENTS.try_receive() {
+        defmt::debug!("Discarding event of abandoned operation {}", outer.handle);
+    }
+}
+
+/// Kind of a bump allocator for RSSI data that doesn't fit in the events.
 ///
 /// Sized 2400 somewhat arbitrarily because it could take 10 runs of RSSI data.
 static RECVBUF: Mutex<CriticalSectionRawMutex, heapless::Vec<u8, 2400>> =
     Mutex::new(heapless::Vec::new());
 
+/// Received PCCs and PDCs, which are referenced by [`DectEvent::Pcc`] and [`DectEvent::Pdc`].
+///
+/// Sized to hold a few of the largest PDCs the nRF9151 can receive at its maximum MCS, so that
+/// continuous reception can go on while the application processes a packet.
+static RX_RING: ring::Ring<8192> = ring::Ring::new();
+
 // FIXME here and in DectEvent: I'd much rather just copy the few bytes around rather than
 // repacking and copying; but that's optimization, and right now I want to get things to run.
 //
@@ -34,6 +91,8 @@ static RECVBUF: Mutex<CriticalSectionRawMutex, heapless::Vec<u8, 2400>> =
 #[derive(Debug)]
 struct DectEventOuter {
     time: u64,
+    /// Handle of the operation the event belongs to, if it belongs to one.
+    handle: Option<u32>,
     event: DectEvent,
 }
 
@@ -46,18 +105,16 @@ enum DectEvent {
     Configure,
     TimeGet,
     LatencyGet,
+    Capability(Option<capability::ModemCapability>),
     Completed(PhyResult),
     /// This is both the `EVT_PCC_ERROR` that really is just CRC error, or failures during processing
     /// of a PCC.
     PccError(rx::PccError),
-    /// PCC with time and length inside recvbuf
-    // If we start doing multiple recvs, we can't just upgrade this to a range here and in PCD,
-    // also not to Option<Range> in case it didn't fit, but need to stream it out through a ring
-    // buffer with process-on-the-fly anyway.
-    Pcc(u64, usize),
-    PdcError,
-    /// Length inside recvbuf
-    Pdc(usize),
+    Pcc(rx::ReceivedPcc),
+    /// PDC CRC error, with the transaction ID
+    PdcError(u16),
+    /// PDC with its transaction ID and its record in [`RX_RING`] (unless it did not fit)
+    Pdc(u16, Option<ring::Record>),
     Rssi(u64, Option<core::ops::Range<usize>>),
 }
 
@@ -67,6 +124,7 @@ extern "C" fn dect_event(arg: *const nrfxlib_sys::nrf_modem_dect_phy_event) {
     let arg: &nrfxlib_sys::nrf_modem_dect_phy_event = unsafe { &*arg };
 
     defmt::trace!("Handler called: id {}, time {}", arg.id, arg.time);
+    let mut handle = None;
     let event = match arg.id {
         nrfxlib_sys::nrf_modem_dect_phy_event_id_NRF_MODEM_DECT_PHY_EVT_INIT => {
             // SAFETY: Checked the discriminator
@@ -128,6 +186,7 @@ extern "C" fn dect_event(arg: *const nrfxlib_sys::nrf_modem_dect_phy_event) {
                 rssi.carrier,
                 meas.len(),
             );
+            handle = Some(rssi.handle);
 
             if let Ok(mut recvbuf) = RECVBUF.try_lock() {
                 let start = recvbuf.len();
@@ -150,6 +209,7 @@ extern "C" fn dect_event(arg: *const nrfxlib_sys::nrf_modem_dect_phy_event) {
                 op.temp,
                 op.voltage
             );
+            handle = Some(op.handle);
             // Go into different queue?
             DectEvent::Completed(op.err.into_phy_result())
         }
@@ -166,6 +226,7 @@ extern "C" fn dect_event(arg: *const nrfxlib_sys::nrf_modem_dect_phy_event) {
         nrfxlib_sys::nrf_modem_dect_phy_event_id_NRF_MODEM_DECT_PHY_EVT_PCC => 'eventresult: {
             // SAFETY: Checked the discriminator
             let pcc = unsafe { &arg.__bindgen_anon_1.pcc };
+            handle = Some(pcc.handle);
 
             let header_len = match pcc.phy_type {
                 0 => 5,
@@ -187,17 +248,22 @@ extern "C" fn dect_event(arg: *const nrfxlib_sys::nrf_modem_dect_phy_event) {
                 header
             );
 
-            let mut recvbuf = RECVBUF
-                .try_lock()
-                .expect("Was checked when doing a request");
-
-            assert_eq!(recvbuf.len(), 0);
-            recvbuf
-                .extend_from_slice(header)
-                .expect("Length is small enough to always fit");
-            DectEvent::Pcc(pcc.stf_start_time, header.len())
+            DectEvent::Pcc(rx::ReceivedPcc {
+                time: pcc.stf_start_time,
+                metadata: rx::RxMetadata {
+                    handle: pcc.handle,
+                    transaction_id: pcc.transaction_id,
+                    header_status: rx::HeaderStatus::from_nrf(pcc.header_status),
+                    rssi_2: pcc.rssi_2,
+                    snr: pcc.snr,
+                },
+                record: RX_RING.push(header),
+            })
         }
         nrfxlib_sys::nrf_modem_dect_phy_event_id_NRF_MODEM_DECT_PHY_EVT_PCC_ERROR => {
+            // SAFETY: Checked the discriminator
+            let pcc_crc_err = unsafe { &arg.__bindgen_anon_1.pcc_crc_err };
+            handle = Some(pcc_crc_err.handle);
             DectEvent::PccError(rx::PccError::CrcError)
         }
         nrfxlib_sys::nrf_modem_dect_phy_event_id_NRF_MODEM_DECT_PHY_EVT_PDC => {
@@ -211,19 +277,32 @@ extern "C" fn dect_event(arg: *const nrfxlib_sys::nrf_modem_dect_phy_event) {
                 pdc.transaction_id,
                 data,
             );
+            handle = Some(pdc.handle);
 
-            let mut recvbuf = RECVBUF
-                .try_lock()
-                .expect("Was checked when doing a request");
-
-            // Either it fits or it doesn't; the user will see when trying to access the buffer up
-            // to it.
-            // FIXME: Does it makes ense to store it as far as possible?
-            let _ = recvbuf.extend_from_slice(data);
-            DectEvent::Pdc(data.len())
+            // Either it fits or it doesn't; the user will see when trying to access it.
+            DectEvent::Pdc(pdc.transaction_id, RX_RING.push(data))
         }
         nrfxlib_sys::nrf_modem_dect_phy_event_id_NRF_MODEM_DECT_PHY_EVT_PDC_ERROR => {
-            DectEvent::PdcError
+            // SAFETY: Checked the discriminator
+            let pdc_crc_err = unsafe { &arg.__bindgen_anon_1.pdc_crc_err };
+            handle = Some(pdc_crc_err.handle);
+            DectEvent::PdcError(pdc_crc_err.transaction_id)
+        }
+        nrfxlib_sys::nrf_modem_dect_phy_event_id_NRF_MODEM_DECT_PHY_EVT_CAPABILITY => {
+            // SAFETY: Checked the discriminator
+            let capability_get = unsafe { &arg.__bindgen_anon_1.capability_get };
+            assert_eq!(
+                capability_get.err,
+                nrfxlib_sys::nrf_modem_dect_phy_err_NRF_MODEM_DECT_PHY_SUCCESS,
+            );
+            // SAFETY: Implied by the C API
+            let capability = unsafe { &*capability_get.capability };
+            defmt::trace!(
+                "Capabilities: version {}, {} variants",
+                capability.dect_version,
+                capability.variant_count
+            );
+            DectEvent::Capability(capability::ModemCapability::from_nrf(capability))
         }
         nrfxlib_sys::nrf_modem_dect_phy_event_id_NRF_MODEM_DECT_PHY_EVT_LATENCY => {
             // SAFETY: Checked the discriminator
@@ -249,15 +328,54 @@ extern "C" fn dect_event(arg: *const nrfxlib_sys::nrf_modem_dect_phy_event) {
             return;
         }
     };
-    DECT_EVENTS
-        .try_send(DectEventOuter {
-            event,
-            time: arg.time,
-        })
-        .expect("Queue is managed");
+    if !matches!(event, DectEvent::Completed(_))
+        && DECT_EVENTS.free_capacity() <= COMPLETION_RESERVE
+    {
+        // The application does not keep up with reception. Dropping the PDC event of a PCC that
+        // was delivered makes that PCC's PDC show as not received; a PDC event whose PCC event
+        // was dropped is skipped by the receiver.
+        defmt::warn!("Event queue full, dropping event");
+        if matches!(event, DectEvent::Pcc(_) | DectEvent::PccError(_)) {
+            LOST_PACKETS.fetch_add(1, Ordering::Relaxed);
+        }
+        return;
+    }
+    let outer = DectEventOuter {
+        event,
+        handle,
+        time: arg.time,
+    };
+    if DECT_EVENTS.try_send(outer).is_err() {
+        // Only happens if more completions of abandoned operations arrive during an operation
+        // than are reserved. If this was the completion of the current operation, that operation
+        // does not finish, but panicking here would take down the modem callback.
+        defmt::error!(
+            "Event queue full, dropping completion of operation {}",
+            handle
+        );
+    }
 }
 
-pub struct DectPhy(());
+/// Receives the next event that belongs to one of the given operations, or to none.
+///
+/// Events of any other operation are left over from operations that were abandoned, and are
+/// discarded.
+async fn receive_event(handles: &[u32]) -> DectEventOuter {
+    loop {
+        let outer = DECT_EVENTS.receive().await;
+        match outer.handle {
+            Some(handle) if !handles.contains(&handle) => {
+                defmt::debug!("Discarding event of abandoned operation {}", handle);
+            }
+            _ => return outer,
+        }
+    }
+}
+
+pub struct DectPhy {
+    /// Capabilities the modem reported at initialization, if they could be expressed.
+    capability: Option<capability::ModemCapability>,
+}
 
 impl DectPhy {
     /// Starts the NRF Modem library with a manually specified memory layout
@@ -301,9 +419,18 @@ impl DectPhy {
             panic!("Sequence violation");
         };
 
+        unsafe { nrfxlib_sys::nrf_modem_dect_phy_capability_get() }.into_result()?;
+        let DectEventOuter {
+            event: DectEvent::Capability(capability),
+            ..
+        } = DECT_EVENTS.receive().await
+        else {
+            panic!("Sequence violation");
+        };
+
         // FIXME take parameters
         let params = nrfxlib_sys::nrf_modem_dect_phy_config_params {
-            band_group_index: 0,
+            band_group_index: carrier::BAND_GROUP_INDEX,
             harq_rx_process_count: 4,
             harq_rx_expiry_time_us: 1000000,
         };
@@ -328,7 +455,7 @@ impl DectPhy {
             panic!("Sequence violation");
         };
 
-        Ok(Self(()))
+        Ok(Self { capability })
     }
 
     pub async fn time_get(&mut self) -> Result<u64, Error> {
@@ -337,7 +464,8 @@ impl DectPhy {
         let DectEventOuter {
             event: DectEvent::TimeGet,
             time,
-        } = DECT_EVENTS.receive().await
+            ..
+        } = receive_event(&[]).await
         else {
             panic!("Sequence violation");
         };
@@ -356,68 +484,4 @@ impl DectPhy {
         recvbuf.clear();
         drop(recvbuf);
     }
-
-    /// Transmit a message at the indicated time, or immediately if `start_time` is 0.
-    ///
-    /// The `network_id` influences scrambling. Pass in the full 32-bit network ID; this function
-    /// picks it apart depending on the PCC length. Beware that this is required to be non-zero.
-    pub async fn tx(
-        &mut self,
-        start_time: u64,
-        channel: u16,
-        network_id: u32,
-        pcc: &[u8],
-        pdc: &[u8],
-    ) -> Result<(), MixedError> {
-        let phy_type = match pcc.len() {
-            5 => 0,
-            10 => 1,
-            _ => panic!("Not a valid header length"),
-        };
-
-        // The PHY function is documented to require this, and will indeed not transmit.
-        //
-        // But expressing this in the type would be odd (the full value is computed of parts where
-        // it is not clear whose resposibility it is to not be zero) for practical deployments. (Is
-        // it really the random lower 8 bits that need to special-case if the upper 24 are all-zero?)
-        //
-        // Handling this as an error seems to be most practical, as it won't take down the whole
-        // system but will not go silently either.
-        if network_id == 0 {
-            return Err(MixedError::UsageError);
-        }
-
-        unsafe {
-            // FIXME: everything
-            nrfxlib_sys::nrf_modem_dect_phy_tx(&nrfxlib_sys::nrf_modem_dect_phy_tx_params {
-                start_time,
-                handle: 2468,
-                // FIXME: Verify that libmodem or the network core does the >> 8 / & 0xff.
-                //
-                // (Probably: otherwise, the "must not be zero" can not be upheld).
-                network_id,
-                phy_type,
-                lbt_rssi_threshold_max: 0, // see below
-                carrier: channel,
-                lbt_period: 0, // BIG FIXME
-                // The object may be smaller than expected for phy_header, but then, phy_type tells
-                // to only access the smaller struct fields anyway.
-                phy_header: pcc.as_ptr() as _,
-                bs_cqi: nrfxlib_sys::NRF_MODEM_DECT_PHY_BS_CQI_NOT_USED as _,
-                // Missing `const` in C? They won't really write in there, will they?
-                data: pdc.as_ptr().cast_mut(),
-                data_size: pdc.len() as _,
-            })
-        }
-        .into_result()
-        .map_err(MixedError::General)?;
-
-        match DECT_EVENTS.receive().await {
-            DectEventOuter {
-                event: DectEvent::Completed(e),
-                ..
-            } => e.map_err(MixedError::Phy),
-            _ => panic!("Sequence violation"),
-        }
-    }
 }
@@ -0,0 +1,125 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Ring buffer for received PCC and PDC data.
+//!
+//! The event handler pushes records into the ring, and reports their position through the event
+//! queue. The receiving side accesses the records in the order of the events, and releases them
+//! in that order once they are not used any more, which makes their space available to the event
+//! handler again.
+//!
+//! Positions are counted in bytes ever written (wrapping around at `usize::MAX`); a record's
+//! position in the buffer is that count modulo the buffer size. Records are stored contiguously,
+//! so space at the end of the buffer is skipped if a record does not fit there.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Position of a record in a [`Ring`].
+#[derive(Debug, defmt::Format, Copy, Clone)]
+pub(super) struct Record {
+    start: usize,
+    len: usize,
+}
+
+impl Record {
+    /// Position up to which the ring can be released when this record is not used any more.
+    pub(super) fn end(self) -> usize {
+        self.start.wrapping_add(self.len)
+    }
+}
+
+/// Single producer, single consumer ring buffer of `N` bytes.
+///
+/// The producer is the event handler; the consumer is whoever holds the [`super::DectPhy`].
+pub(super) struct Ring<const N: usize> {
+    buf: UnsafeCell<[u8; N]>,
+    /// Position up to which data was written; only altered by the producer.
+    head: AtomicUsize,
+    /// Position up to which data was released; only altered by the consumer.
+    tail: AtomicUsize,
+}
+
+// SAFETY: The producer only writes to the region outside of tail..head, and the consumer only
+// reads from inside it; the atomics' orderings ensure that data is written before the region is
+// extended, and read before it is released.
+unsafe impl<const N: usize> Sync for Ring<N> {}
+
+impl<const N: usize> Ring<N> {
+    pub(super) const fn new() -> Self {
+        const {
+            assert!(
+                N.is_power_of_two(),
+                "Positions wrap around usize::MAX, which needs to be aligned with the buffer size"
+            );
+        }
+        Self {
+            buf: UnsafeCell::new([0; N]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Appends a record; returns `None` if there is no space for it.
+    ///
+    /// This must only be called by the producer.
+    pub(super) fn push(&self, data: &[u8]) -> Option<Record> {
+        let len = data.len();
+        if len > N {
+            return None;
+        }
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        let offset = head % N;
+        let start = if offset + len > N {
+            head.wrapping_add(N - offset)
+        } else {
+            head
+        };
+        let end = start.wrapping_add(len);
+        if end.wrapping_sub(tail) > N {
+            return None;
+        }
+        // SAFETY: The region is in bounds (checked above), and not in tail..head, so it is not
+        // accessed by the consumer.
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                self.buf.get().cast::<u8>().add(start % N),
+                len,
+            );
+        }
+        self.head.store(end, Ordering::Release);
+        Some(Record { start, len })
+    }
+
+    /// Accesses a record.
+    ///
+    /// # Safety
+    ///
+    /// The record must have been produced by [`Self::push()`] on this ring, and must not have been
+    /// released yet.
+    pub(super) unsafe fn get(&self, record: Record) -> &[u8] {
+        // SAFETY: As per the caller's promise, the producer will not write there until the record
+        // is released.
+        unsafe {
+            core::slice::from_raw_parts(
+                self.buf.get().cast::<u8>().add(record.start % N),
+                record.len,
+            )
+        }
+    }
+
+    /// Releases all records up to the given position (typically a [`Record::end()`]).
+    ///
+    /// This must only be called by the consumer, in the order in which records were produced.
+    pub(super) fn release(&self, end: usize) {
+        self.tail.store(end, Ordering::Release);
+    }
+
+    /// Releases all records.
+    ///
+    /// This must only be called by the consumer, when no record is in use.
+    pub(super) fn reset(&self) {
+        self.release(self.head.load(Ordering::Acquire));
+    }
+}
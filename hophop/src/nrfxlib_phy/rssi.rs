@@ -4,7 +4,7 @@
 use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::MutexGuard};
 use nrf_modem::{ErrorSource, nrfxlib_sys};
 
-use super::{DECT_EVENTS, DectEvent, DectPhy, MixedError, RECVBUF};
+use super::{Carrier, DectEvent, DectPhy, MixedError, RECVBUF};
 
 /// Resulting data slice of a single RSSI measurement.
 ///
@@ -25,7 +25,7 @@ impl RssiResult<'_> {
 }
 
 impl DectPhy {
-    pub async fn rssi(&mut self, carrier: u16) -> Result<(u64, RssiResult<'_>), MixedError> {
+    pub async fn rssi(&mut self, carrier: Carrier) -> Result<(u64, RssiResult<'_>), MixedError> {
         self.clear_recvbuf();
 
         // Relevant DECT constant timing parameters are 1 frame = 10ms, each 10ms frame is composed
@@ -42,10 +42,12 @@ impl DectPhy {
         // - Requesting a duration of N gives 5*N readings. This is given in subslots, which for
         //   µ=1 is 2 subslots per slot, and thus matches 10 readings per slot, 5 per subslot.
 
+        super::discard_stale_events();
+        let handle = super::new_handle();
         let params = nrfxlib_sys::nrf_modem_dect_phy_rssi_params {
             start_time: 0,
-            handle: 1234567,
-            carrier,
+            handle,
+            carrier: carrier.get(),
             duration: 48, // in subslots; 1 full report
             reporting_interval: nrfxlib_sys::nrf_modem_dect_phy_rssi_interval_NRF_MODEM_DECT_PHY_RSSI_INTERVAL_24_SLOTS, // 24 slots = 10ms
         };
@@ -54,7 +56,7 @@ impl DectPhy {
         let mut result = None;
 
         loop {
-            match DECT_EVENTS.receive().await.event {
+            match super::receive_event(&[handle]).await.event {
                 DectEvent::Rssi(start, range) => {
                     debug_assert!(result.is_none(), "Sequence violation");
                     result = Some((
@@ -72,8 +74,8 @@ impl DectPhy {
 
         let Some(result) = result else {
             // FIXME: Verify that it's an actual completion error that happens when requesting an
-            // unsupported channel.
-            panic!("Sequence violation");
+            // unsupported channel. Either way, this should not take down the system.
+            return Err(MixedError::NoResult);
         };
 
         Ok((
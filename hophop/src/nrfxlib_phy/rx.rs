@@ -1,16 +1,20 @@
 // SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::MutexGuard};
+use core::sync::atomic::Ordering;
+
 use nrf_modem::{ErrorSource, nrfxlib_sys};
 
-use super::{DECT_EVENTS, DectEvent, DectPhy, MixedError, RECVBUF};
+use super::ring::Record;
+use super::{Carrier, DectEvent, DectPhy, LOST_PACKETS, MixedError, RX_RING};
 
 #[derive(Debug, defmt::Format, Copy, Clone)]
 #[non_exhaustive]
 pub enum PccError {
     CrcError,
     UnexpectedEventDetails,
+    /// The receive buffer was full.
+    OutOfSpace,
 }
 
 #[derive(Debug, defmt::Format, Copy, Clone)]
@@ -24,93 +28,336 @@ pub enum PdcError {
     PccError(PccError),
 }
 
-/// Details of a [`RecvResult`] that did result in data being received.
-#[derive(Copy, Clone)]
-pub struct RecvOk {
-    pub pcc_time: u64,
-    pub pcc_len: usize,
-    pub pdc_len: Result<usize, PdcError>,
+/// Whether the PDC announced by a PCC is being received.
+#[derive(Debug, defmt::Format, Copy, Clone, PartialEq, Eq)]
+pub enum HeaderStatus {
+    /// The header is valid, and its PDC is received.
+    Valid,
+    /// The header content is not usable (e.g. a reserved value); no PDC is received.
+    Invalid,
+    /// The header is valid, but the receive operation ends before its PDC.
+    ValidRxEnd,
+}
+
+impl HeaderStatus {
+    pub(super) fn from_nrf(status: nrfxlib_sys::nrf_modem_dect_phy_hdr_status) -> Self {
+        match status {
+            nrfxlib_sys::nrf_modem_dect_phy_hdr_status_NRF_MODEM_DECT_PHY_HDR_STATUS_VALID => {
+                Self::Valid
+            }
+            nrfxlib_sys::nrf_modem_dect_phy_hdr_status_NRF_MODEM_DECT_PHY_HDR_STATUS_VALID_RX_END => {
+                Self::ValidRxEnd
+            }
+            _ => Self::Invalid,
+        }
+    }
+}
+
+/// Reception details that the modem reports along with a PCC.
+#[derive(Debug, defmt::Format, Copy, Clone)]
+pub struct RxMetadata {
+    pub(super) handle: u32,
+    pub(super) transaction_id: u16,
+    pub(super) header_status: HeaderStatus,
+    /// In units of 0.5 dBm
+    pub(super) rssi_2: i16,
+    /// In units of 0.25 dB
+    pub(super) snr: i16,
 }
 
-/// Result of a single receive operation.
+impl RxMetadata {
+    /// Handle of the receive operation during which the packet was received.
+    pub fn handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Identifier by which the modem associates the PDC with the PCC.
+    pub fn transaction_id(&self) -> u16 {
+        self.transaction_id
+    }
+
+    pub fn header_status(&self) -> HeaderStatus {
+        self.header_status
+    }
+
+    /// Received signal strength (RSSI-2) of the PCC in dBm, at a resolution of 0.5 dBm.
+    pub fn rssi_2(&self) -> f32 {
+        f32::from(self.rssi_2) / 2.0
+    }
+
+    /// Signal to noise ratio of the PCC in dB, at a resolution of 0.25 dB.
+    pub fn snr(&self) -> f32 {
+        f32::from(self.snr) / 4.0
+    }
+}
+
+/// A PCC as reported through [`DectEvent::Pcc`].
+#[derive(Debug, Copy, Clone)]
+pub(super) struct ReceivedPcc {
+    pub(super) time: u64,
+    pub(super) metadata: RxMetadata,
+    /// Record in [`RX_RING`] (unless it did not fit)
+    pub(super) record: Option<Record>,
+}
+
+/// A received packet, or what could be received of it.
 ///
-/// This keeps a lock on the receive buffer, and must therefore be dropped before the next attempt
-/// to perform any other operation.
+/// The data is kept in the receive buffer until this is dropped.
 pub struct RecvResult<'a> {
-    data: MutexGuard<'static, CriticalSectionRawMutex, heapless::Vec<u8, 2400>>,
-    indices: Result<RecvOk, PccError>,
-    // This ensures that a .recv() result is used before the next attempt to receive something (as
-    // that would panic around locking RECV_BUF).
+    pcc: Result<ReceivedPcc, PccError>,
+    pdc: Result<Record, PdcError>,
+    lost_before: u32,
+    // This ensures that the records are released before the next operation resets the buffer,
+    // and in the order in which they were received.
     _phantom: core::marker::PhantomData<&'a mut ()>,
 }
 
 impl RecvResult<'_> {
+    /// Builds a result from the PCC event (or PCC error), and the PDC event (or PDC error) if one
+    /// was received.
+    ///
+    /// The PDC must have been matched to the PCC by the caller.
+    fn new(
+        pcc: Result<ReceivedPcc, PccError>,
+        pdc: Option<Result<Option<Record>, PdcError>>,
+    ) -> Self {
+        let pdc = match (pcc, pdc) {
+            (Err(e), _) => Err(PdcError::PccError(e)),
+            (Ok(_), None) => Err(PdcError::NotReceived),
+            (Ok(_), Some(Ok(Some(record)))) => Ok(record),
+            (Ok(_), Some(Ok(None))) => Err(PdcError::OutOfSpace),
+            (Ok(_), Some(Err(e))) => Err(e),
+        };
+        Self {
+            pcc,
+            pdc,
+            lost_before: 0,
+            _phantom: core::marker::PhantomData,
+        }
+    }
+
+    /// Number of packets that were lost since the previously reported one, because the
+    /// application did not keep up with receiving them.
+    ///
+    /// This is only ever non-zero on packets of an [`RxStream`].
+    pub fn lost_before(&self) -> u32 {
+        self.lost_before
+    }
+
     pub fn pcc_time(&self) -> Result<u64, PccError> {
-        Ok(self.indices?.pcc_time)
+        Ok(self.pcc?.time)
+    }
+    /// Reception details of the packet.
+    ///
+    /// These are available even if the PCC itself did not fit in the receive buffer.
+    pub fn metadata(&self) -> Result<RxMetadata, PccError> {
+        Ok(self.pcc?.metadata)
     }
     pub fn pcc(&self) -> Result<&[u8], PccError> {
-        Ok(&self.data[..self.indices?.pcc_len])
+        let record = self.pcc?.record.ok_or(PccError::OutOfSpace)?;
+        // SAFETY: Records are only released when self is dropped.
+        Ok(unsafe { RX_RING.get(record) })
     }
     pub fn pdc(&self) -> Result<&[u8], PdcError> {
-        let pcc_and_rest = self.indices.map_err(PdcError::PccError)?;
-        let start = pcc_and_rest.pcc_len;
-        let len = pcc_and_rest.pdc_len?;
-        self.data
-            .get(start..start + len)
-            .ok_or(PdcError::OutOfSpace)
+        // SAFETY: Records are only released when self is dropped.
+        Ok(unsafe { RX_RING.get(self.pdc?) })
     }
 }
 
-impl DectPhy {
-    // FIXME: heapless is not great for signature yet
-    pub async fn rx(&mut self) -> Result<Option<RecvResult<'_>>, MixedError> {
-        self.clear_recvbuf();
-
-        unsafe {
-            // FIXME: everything
-            nrfxlib_sys::nrf_modem_dect_phy_rx(&nrfxlib_sys::nrf_modem_dect_phy_rx_params {
-                start_time: 0,
-                handle: 54321,
-                network_id: 0x12345678, // like dect_shell defaults
-                mode: nrfxlib_sys::nrf_modem_dect_phy_rx_mode_NRF_MODEM_DECT_PHY_RX_MODE_SINGLE_SHOT,
-                rssi_interval: nrfxlib_sys::nrf_modem_dect_phy_rssi_interval_NRF_MODEM_DECT_PHY_RSSI_INTERVAL_OFF,
-                link_id: nrfxlib_sys::nrf_modem_dect_phy_link_id {
-                    short_network_id: 0,
-                    short_rd_id: 0,
-                },
-                rssi_level: 0,
-                carrier: 1665, // like dect_shell ping default
-                // ~ 1 second
-                duration: 70000000,
-                filter: nrfxlib_sys::nrf_modem_dect_phy_rx_filter {
-                    short_network_id: 0,
-                    is_short_network_id_used: 0,
-                    receiver_identity: 0,
-                },
-            })
+impl Drop for RecvResult<'_> {
+    fn drop(&mut self) {
+        let last = self.pdc.ok().or(self.pcc.ok().and_then(|pcc| pcc.record));
+        if let Some(last) = last {
+            RX_RING.release(last.end());
+        }
+    }
+}
+
+/// Mode of a receive operation.
+#[derive(Debug, defmt::Format, Copy, Clone, PartialEq, Eq)]
+pub enum RxMode {
+    /// Receive operation ends after the first received packet (or at the end of the duration).
+    SingleShot,
+    /// Receive operation continues after a packet, but ends at the end of the duration.
+    ///
+    /// Unlike [`Self::Continuous`], this allows the modem to skip synchronization for packets that
+    /// are expected in a known schedule.
+    Semicontinuous,
+    /// Receive operation continues for the whole duration.
+    Continuous,
+}
+
+impl RxMode {
+    fn to_nrf(self) -> nrfxlib_sys::nrf_modem_dect_phy_rx_mode {
+        match self {
+            Self::SingleShot => {
+                nrfxlib_sys::nrf_modem_dect_phy_rx_mode_NRF_MODEM_DECT_PHY_RX_MODE_SINGLE_SHOT
+            }
+            Self::Semicontinuous => {
+                nrfxlib_sys::nrf_modem_dect_phy_rx_mode_NRF_MODEM_DECT_PHY_RX_MODE_SEMICONTINUOUS
+            }
+            Self::Continuous => {
+                nrfxlib_sys::nrf_modem_dect_phy_rx_mode_NRF_MODEM_DECT_PHY_RX_MODE_CONTINUOUS
+            }
+        }
+    }
+}
+
+/// Parameters of a receive operation.
+///
+/// The defaults set up by [`RxParams::new()`] start receiving immediately for about 1 second in
+/// single-shot mode, without filtering and without RSSI reports.
+#[derive(Debug, defmt::Format, Copy, Clone)]
+pub struct RxParams {
+    start_time: u64,
+    carrier: Carrier,
+    duration: u32,
+    network_id: u32,
+    mode: RxMode,
+    short_network_id: Option<u8>,
+    receiver_identity: Option<u16>,
+}
+
+impl RxParams {
+    /// Creates parameters to receive on a carrier in a network.
+    ///
+    /// The `network_id` influences descrambling; pass in the full 32-bit network ID.
+    pub fn new(carrier: Carrier, network_id: u32) -> Self {
+        Self {
+            start_time: 0,
+            carrier,
+            duration: 70_000_000,
+            network_id,
+            mode: RxMode::SingleShot,
+            short_network_id: None,
+            receiver_identity: None,
+        }
+    }
+
+    /// Sets the modem time at which to start receiving; 0 starts immediately.
+    pub fn start_time(mut self, start_time: u64) -> Self {
+        self.start_time = start_time;
+        self
+    }
+
+    /// Sets the duration of the operation, in modem time ticks.
+    pub fn duration(mut self, duration: u32) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets the receive mode.
+    pub fn mode(mut self, mode: RxMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Only reports packets whose PCC carries the given Short Network ID.
+    pub fn short_network_id_filter(mut self, short_network_id: u8) -> Self {
+        self.short_network_id = Some(short_network_id);
+        self
+    }
+
+    /// Only reports packets whose Type 2 PCC is addressed to the given Short RD ID.
+    ///
+    /// Packets with a Type 1 PCC, and broadcast packets, are still reported.
+    ///
+    /// # Errors
+    ///
+    /// The value 0 is rejected, because libmodem uses it to express that no filter is set.
+    pub fn receiver_identity_filter(mut self, receiver_identity: u16) -> Result<Self, MixedError> {
+        if receiver_identity == 0 {
+            return Err(MixedError::UsageError);
         }
-        .into_result()?;
+        self.receiver_identity = Some(receiver_identity);
+        Ok(self)
+    }
+
+    /// Checks that the parameters are usable in an operation that receives a single packet.
+    pub(super) fn check_single_shot(&self) -> Result<(), MixedError> {
+        if self.mode != RxMode::SingleShot {
+            return Err(MixedError::UsageError);
+        }
+        Ok(())
+    }
 
+    pub(super) fn to_nrf(self, handle: u32) -> nrfxlib_sys::nrf_modem_dect_phy_rx_params {
+        nrfxlib_sys::nrf_modem_dect_phy_rx_params {
+            start_time: self.start_time,
+            handle,
+            network_id: self.network_id,
+            mode: self.mode.to_nrf(),
+            // FIXME: Offer RSSI reports once there is a way to deliver them to the application
+            // (e.g. between the packets of an RxStream).
+            rssi_interval:
+                nrfxlib_sys::nrf_modem_dect_phy_rssi_interval_NRF_MODEM_DECT_PHY_RSSI_INTERVAL_OFF,
+            link_id: nrfxlib_sys::nrf_modem_dect_phy_link_id {
+                short_network_id: 0,
+                short_rd_id: 0,
+            },
+            rssi_level: 0,
+            carrier: self.carrier.get(),
+            duration: self.duration,
+            filter: nrfxlib_sys::nrf_modem_dect_phy_rx_filter {
+                short_network_id: self.short_network_id.unwrap_or(0),
+                is_short_network_id_used: self.short_network_id.is_some().into(),
+                receiver_identity: self.receiver_identity.unwrap_or(0),
+            },
+        }
+    }
+}
+
+impl DectPhy {
+    /// Receives a single packet.
+    ///
+    /// Only [`RxMode::SingleShot`] is accepted here; use [`Self::rx_stream()`] for the other
+    /// modes.
+    pub async fn rx(&mut self, params: RxParams) -> Result<Option<RecvResult<'_>>, MixedError> {
+        params.check_single_shot()?;
+
+        RX_RING.reset();
+
+        super::discard_stale_events();
+        let handle = super::new_handle();
+        unsafe { nrfxlib_sys::nrf_modem_dect_phy_rx(&params.to_nrf(handle)) }.into_result()?;
+
+        self.single_reception(handle).await
+    }
+
+    /// Collects the events of a single-shot receive operation that was started with `handle`,
+    /// until it completes.
+    pub(super) async fn single_reception(
+        &mut self,
+        handle: u32,
+    ) -> Result<Option<RecvResult<'_>>, MixedError> {
         let mut pcc = None;
         let mut pdc = None;
 
         loop {
-            match DECT_EVENTS.receive().await.event {
-                DectEvent::Pcc(start, pcc_len) => {
+            match super::receive_event(&[handle]).await.event {
+                DectEvent::Pcc(received) => {
                     debug_assert!(pcc.is_none(), "Sequence violation");
-                    pcc = Some(Ok((start, pcc_len)));
+                    pcc = Some(Ok(received));
                 }
                 DectEvent::PccError(e) => {
                     debug_assert!(pcc.is_none(), "Sequence violation");
                     pcc = Some(Err(e));
                 }
-                DectEvent::Pdc(pcd_len) => {
-                    debug_assert!(pdc.is_none(), "Sequence violation");
-                    pdc = Some(Ok(pcd_len));
+                DectEvent::Pdc(transaction_id, record) => {
+                    if matches_pcc(pcc, transaction_id) {
+                        debug_assert!(pdc.is_none(), "Sequence violation");
+                        pdc = Some(Ok(record));
+                    } else {
+                        defmt::warn!("Discarding PDC of transaction {}", transaction_id);
+                    }
                 }
-                DectEvent::PdcError => {
-                    debug_assert!(pdc.is_none(), "Sequence violation");
-                    pdc = Some(Err(PdcError::CrcError));
+                DectEvent::PdcError(transaction_id) => {
+                    if matches_pcc(pcc, transaction_id) {
+                        debug_assert!(pdc.is_none(), "Sequence violation");
+                        pdc = Some(Err(PdcError::CrcError));
+                    } else {
+                        defmt::warn!("Discarding PDC error of transaction {}", transaction_id);
+                    }
                 }
                 DectEvent::Completed(Ok(())) => {
                     break;
@@ -120,28 +367,140 @@ impl DectPhy {
             }
         }
 
-        let result = match (pcc, pdc) {
-            (None, None) => return Ok(None),
-            (Some(Err(e)), None) => Err(e),
-            (Some(Ok((pcc_time, pcc_len))), None) => Ok(RecvOk {
-                pcc_time,
-                pcc_len,
-                pdc_len: Err(PdcError::NotReceived),
-            }),
-            (Some(Ok((pcc_time, pcc_len))), Some(pdc_len)) => Ok(RecvOk {
-                pcc_time,
-                pcc_len,
-                pdc_len,
-            }),
+        match (pcc, pdc) {
+            (None, None) => Ok(None),
+            (Some(Err(e)), None) => Ok(Some(RecvResult::new(Err(e), None))),
+            (Some(Ok(pcc)), pdc) => Ok(Some(RecvResult::new(Ok(pcc), pdc))),
             _ => panic!("Sequence violation"),
-        };
+        }
+    }
 
-        Ok(Some(RecvResult {
-            data: RECVBUF
-                .try_lock()
-                .expect("Was checked before, and ISR users release this before returning"),
-            indices: result,
+    /// Starts a receive operation that reports any number of packets.
+    ///
+    /// This is mainly useful with [`RxMode::Continuous`] or [`RxMode::Semicontinuous`]. Packets
+    /// are buffered while the application processes earlier ones, and their space is freed when
+    /// their [`RecvResult`] is dropped.
+    pub async fn rx_stream(&mut self, params: RxParams) -> Result<RxStream<'_>, MixedError> {
+        RX_RING.reset();
+        LOST_PACKETS.store(0, Ordering::Relaxed);
+
+        super::discard_stale_events();
+        let handle = super::new_handle();
+        unsafe { nrfxlib_sys::nrf_modem_dect_phy_rx(&params.to_nrf(handle)) }.into_result()?;
+
+        Ok(RxStream {
+            handle,
+            lookahead: None,
+            finished: false,
             _phantom: core::marker::PhantomData,
-        }))
+        })
+    }
+}
+
+/// Whether a PDC with the given transaction ID belongs to the PCC that was received.
+fn matches_pcc(pcc: Option<Result<ReceivedPcc, PccError>>, transaction_id: u16) -> bool {
+    matches!(pcc, Some(Ok(received)) if received.metadata.transaction_id == transaction_id)
+}
+
+/// A running receive operation; see [`DectPhy::rx_stream()`].
+///
+/// Dropping this before it is finished stops the operation without waiting for the modem to
+/// confirm that; any of its events that arrive later are discarded by subsequent operations.
+/// [`Self::stop()`] also waits for the operation to end.
+pub struct RxStream<'a> {
+    /// Handle under which the operation was started.
+    handle: u32,
+    /// An event that was received while looking for the PDC of a PCC, and belongs to what comes
+    /// next.
+    lookahead: Option<DectEvent>,
+    /// Whether the operation's completion event was received.
+    finished: bool,
+    _phantom: core::marker::PhantomData<&'a mut DectPhy>,
+}
+
+impl RxStream<'_> {
+    async fn receive_event(&mut self) -> DectEvent {
+        match self.lookahead.take() {
+            Some(event) => event,
+            None => super::receive_event(&[self.handle]).await.event,
+        }
+    }
+
+    /// Waits for the next packet.
+    ///
+    /// This returns `None` once the operation has ended, e.g. because its duration has passed.
+    /// A packet whose PCC announced a PDC that was not received is only reported once the next
+    /// event arrives.
+    pub async fn next_reception(&mut self) -> Option<Result<RecvResult<'_>, MixedError>> {
+        if self.finished {
+            return None;
+        }
+
+        let pcc = loop {
+            match self.receive_event().await {
+                DectEvent::Pcc(received) => break Ok(received),
+                DectEvent::PccError(e) => break Err(e),
+                DectEvent::Completed(result) => {
+                    self.finished = true;
+                    return result.err().map(|e| Err(MixedError::Phy(e)));
+                }
+                // A PDC without a PCC happens when the PCC event was dropped because the event
+                // queue was full, which is counted in LOST_PACKETS.
+                DectEvent::Pdc(..) | DectEvent::PdcError(_) => continue,
+                _ => panic!("Sequence violation"),
+            }
+        };
+
+        let pdc = match pcc {
+            Ok(received) if received.metadata.header_status == HeaderStatus::Valid => loop {
+                match self.receive_event().await {
+                    DectEvent::Pdc(transaction_id, record)
+                        if transaction_id == received.metadata.transaction_id =>
+                    {
+                        break Some(Ok(record));
+                    }
+                    DectEvent::PdcError(transaction_id)
+                        if transaction_id == received.metadata.transaction_id =>
+                    {
+                        break Some(Err(PdcError::CrcError));
+                    }
+                    // Belonging to no PCC we know of
+                    DectEvent::Pdc(..) | DectEvent::PdcError(_) => continue,
+                    other => {
+                        self.lookahead = Some(other);
+                        break None;
+                    }
+                }
+            },
+            _ => None,
+        };
+
+        let mut result = RecvResult::new(pcc, pdc);
+        result.lost_before = LOST_PACKETS.swap(0, Ordering::Relaxed);
+        Some(Ok(result))
+    }
+
+    /// Stops the operation, and waits until it has ended.
+    ///
+    /// Packets that were received but not yet reported are discarded.
+    pub async fn stop(mut self) -> Result<(), MixedError> {
+        if self.finished {
+            return Ok(());
+        }
+        unsafe { nrfxlib_sys::nrf_modem_dect_phy_rx_stop(self.handle) }.into_result()?;
+        while !matches!(self.receive_event().await, DectEvent::Completed(_)) {}
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl Drop for RxStream<'_> {
+    fn drop(&mut self) {
+        if self.finished || matches!(self.lookahead, Some(DectEvent::Completed(_))) {
+            return;
+        }
+        // If this fails, the operation has just ended by itself. Either way, its remaining
+        // events carry its handle, and are discarded by whichever operation receives next.
+        let _ = unsafe { nrfxlib_sys::nrf_modem_dect_phy_rx_stop(self.handle) }.into_result();
     }
 }
@@ -0,0 +1,316 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use nrf_modem::{ErrorSource, nrfxlib_sys};
+use ts_103_636_utils::timing;
+
+use super::rx::{RecvResult, RxParams};
+use super::{Carrier, DectEvent, DectEventOuter, DectPhy, MixedError, RX_RING};
+
+/// Duration of an OFDM symbol at µ = 1, which is the numerology the modem operates at.
+const SYMBOL: u32 = (timing::subslot(1) / 5) as u32;
+
+/// Range of LBT periods the modem accepts (2 to 110 symbols).
+const LBT_PERIODS: core::ops::RangeInclusive<u32> = (2 * SYMBOL)..=(110 * SYMBOL);
+
+/// Listen-before-talk settings of a transmission.
+///
+/// Before transmitting, the modem measures the RSSI on the carrier for the LBT period. If any
+/// measurement exceeds the busy threshold, the transmission is not sent, and the operation fails
+/// with [`MixedError::ChannelBusy`].
+#[derive(Debug, defmt::Format, Copy, Clone)]
+pub struct Lbt {
+    period: u32,
+    busy_threshold: i8,
+}
+
+impl Lbt {
+    /// Listens for `period` modem time ticks, and considers the channel busy if the RSSI exceeds
+    /// `busy_threshold` dBm.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the period is shorter than 2 or longer than 110 symbols, which is what the
+    /// modem supports.
+    pub fn new(period: u32, busy_threshold: i8) -> Result<Self, MixedError> {
+        if !LBT_PERIODS.contains(&period) {
+            return Err(MixedError::UsageError);
+        }
+        Ok(Self {
+            period,
+            busy_threshold,
+        })
+    }
+
+    /// Listens for a number of subslots (at µ = 1), and considers the channel busy if the RSSI
+    /// exceeds `busy_threshold` dBm.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the period is longer than 22 subslots, which is what the modem supports.
+    pub fn subslots(subslots: u8, busy_threshold: i8) -> Result<Self, MixedError> {
+        Self::new(u32::from(subslots) * 5 * SYMBOL, busy_threshold)
+    }
+}
+
+/// HARQ fields of a transmission, which are placed in its Type 2 PCC (header format 000).
+///
+/// Keeping these with the parameters allows retransmitting the same PCC with a different
+/// redundancy version, as the recipient's modem combines the transmissions of a process.
+#[derive(Debug, defmt::Format, Copy, Clone)]
+pub struct Harq {
+    process_number: u8,
+    new_data_indication: bool,
+    redundancy_version: u8,
+}
+
+impl Harq {
+    /// Creates HARQ fields for a transmission in a HARQ process.
+    ///
+    /// The new data indication is toggled for every new transport block in a process, and kept
+    /// for its retransmissions; the redundancy version typically goes 0, 2, 3, 1.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the process number exceeds 3 bits or the redundancy version exceeds 2 bits.
+    pub fn new(
+        process_number: u8,
+        new_data_indication: bool,
+        redundancy_version: u8,
+    ) -> Result<Self, MixedError> {
+        if process_number > 0x07 || redundancy_version > 0x03 {
+            return Err(MixedError::UsageError);
+        }
+        Ok(Self {
+            process_number,
+            new_data_indication,
+            redundancy_version,
+        })
+    }
+
+    /// Sets header format 000 and the HARQ fields in a Type 2 PCC.
+    fn apply(self, pcc: &mut [u8; 10]) {
+        pcc[0] &= 0x1f;
+        pcc[7] = (pcc[7] & 0xc0)
+            | (self.redundancy_version << 4)
+            | (u8::from(self.new_data_indication) << 3)
+            | self.process_number;
+    }
+}
+
+/// Parameters of a transmit operation.
+///
+/// The defaults set up by [`TxParams::new()`] transmit immediately and without listening before,
+/// and send the PCC unaltered.
+#[derive(Debug, defmt::Format, Copy, Clone)]
+pub struct TxParams {
+    start_time: u64,
+    carrier: Carrier,
+    network_id: u32,
+    lbt: Option<Lbt>,
+    harq: Option<Harq>,
+    bs_cqi: Option<u8>,
+}
+
+impl TxParams {
+    /// Creates parameters to transmit on a carrier in a network.
+    ///
+    /// The `network_id` influences scrambling. Pass in the full 32-bit network ID; it is picked
+    /// apart depending on the PCC length. Beware that this is required to be non-zero.
+    pub fn new(carrier: Carrier, network_id: u32) -> Self {
+        Self {
+            start_time: 0,
+            carrier,
+            network_id,
+            lbt: None,
+            harq: None,
+            bs_cqi: None,
+        }
+    }
+
+    /// Sets the modem time at which to start transmitting; 0 starts immediately.
+    ///
+    /// With [`Self::lbt()`], this is when listening starts, and the transmission follows after
+    /// the LBT period.
+    pub fn start_time(mut self, start_time: u64) -> Self {
+        self.start_time = start_time;
+        self
+    }
+
+    /// Listens before transmitting.
+    pub fn lbt(mut self, lbt: Lbt) -> Self {
+        self.lbt = Some(lbt);
+        self
+    }
+
+    /// Sends the PCC with the given HARQ fields.
+    ///
+    /// This requires a Type 2 PCC; its header format is set to 000.
+    pub fn harq(mut self, harq: Harq) -> Self {
+        self.harq = Some(harq);
+        self
+    }
+
+    /// Sets the buffer status or channel quality indicator that the modem places in HARQ
+    /// feedback it inserts into the PCC.
+    pub fn bs_cqi(mut self, bs_cqi: u8) -> Self {
+        self.bs_cqi = Some(bs_cqi);
+        self
+    }
+
+    /// Builds the libmodem parameters for a PCC and PDC.
+    ///
+    /// The PCC length needs to be checked by the caller, and the buffers need to outlive the use
+    /// of the result.
+    pub(super) fn to_nrf(
+        self,
+        handle: u32,
+        phy_type: u8,
+        pcc: &[u8],
+        pdc: &[u8],
+    ) -> nrfxlib_sys::nrf_modem_dect_phy_tx_params {
+        nrfxlib_sys::nrf_modem_dect_phy_tx_params {
+            start_time: self.start_time,
+            handle,
+            // FIXME: Verify that libmodem or the network core does the >> 8 / & 0xff.
+            //
+            // (Probably: otherwise, the "must not be zero" can not be upheld).
+            network_id: self.network_id,
+            phy_type,
+            // Ignored by libmodem when the period is 0.
+            lbt_rssi_threshold_max: self.lbt.map_or(0, |lbt| lbt.busy_threshold),
+            carrier: self.carrier.get(),
+            lbt_period: self.lbt.map_or(0, |lbt| lbt.period),
+            // The object may be smaller than expected for phy_header, but then, phy_type tells
+            // to only access the smaller struct fields anyway.
+            phy_header: pcc.as_ptr() as _,
+            bs_cqi: self.bs_cqi.map_or(
+                nrfxlib_sys::NRF_MODEM_DECT_PHY_BS_CQI_NOT_USED as _,
+                |bs_cqi| bs_cqi as _,
+            ),
+            // Missing `const` in C? They won't really write in there, will they?
+            data: pdc.as_ptr().cast_mut(),
+            data_size: pdc.len() as _,
+        }
+    }
+}
+
+/// A PCC prepared for transmission: the PCC with any HARQ fields applied, and its PHY type.
+struct PreparedPcc {
+    data: [u8; 10],
+    phy_type: u8,
+}
+
+impl PreparedPcc {
+    fn new(params: &TxParams, pcc: &[u8]) -> Result<Self, MixedError> {
+        // The PHY function is documented to require this, and will indeed not transmit.
+        //
+        // But expressing this in the type would be odd (the full value is computed of parts where
+        // it is not clear whose resposibility it is to not be zero) for practical deployments. (Is
+        // it really the random lower 8 bits that need to special-case if the upper 24 are all-zero?)
+        //
+        // Handling this as an error seems to be most practical, as it won't take down the whole
+        // system but will not go silently either.
+        if params.network_id == 0 {
+            return Err(MixedError::UsageError);
+        }
+
+        let phy_type = match (pcc.len(), params.harq) {
+            (5, None) => 0,
+            (10, _) => 1,
+            // Type 1 PCCs have no HARQ fields; other lengths are no valid PCC.
+            _ => return Err(MixedError::UsageError),
+        };
+        let mut data = [0; 10];
+        data[..pcc.len()].copy_from_slice(pcc);
+        if let Some(harq) = params.harq {
+            harq.apply(&mut data);
+        }
+        Ok(Self { data, phy_type })
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match self.phy_type {
+            0 => &self.data[..5],
+            _ => &self.data,
+        }
+    }
+}
+
+impl DectPhy {
+    /// Transmit a message.
+    pub async fn tx(&mut self, params: TxParams, pcc: &[u8], pdc: &[u8]) -> Result<(), MixedError> {
+        let pcc = PreparedPcc::new(&params, pcc)?;
+
+        super::discard_stale_events();
+        let handle = super::new_handle();
+        unsafe {
+            nrfxlib_sys::nrf_modem_dect_phy_tx(&params.to_nrf(
+                handle,
+                pcc.phy_type,
+                pcc.as_bytes(),
+                pdc,
+            ))
+        }
+        .into_result()
+        .map_err(MixedError::General)?;
+
+        match super::receive_event(&[handle]).await {
+            DectEventOuter {
+                event: DectEvent::Completed(e),
+                ..
+            } => e.map_err(MixedError::from),
+            _ => panic!("Sequence violation"),
+        }
+    }
+
+    /// Transmit a message, and receive a single packet (typically its acknowledgement) in a
+    /// window that opens `rx_offset` modem time ticks after the end of the transmission.
+    ///
+    /// Both are scheduled with the modem in a single operation, which is the only way to meet
+    /// the timing of a response that is expected in the next subslots. The start time set in
+    /// `rx` is overwritten with `rx_offset`, and its mode needs to be
+    /// [`RxMode::SingleShot`](super::RxMode::SingleShot).
+    ///
+    /// If the transmission fails (e.g. with [`MixedError::ChannelBusy`]), this returns without
+    /// receiving.
+    pub async fn tx_rx(
+        &mut self,
+        tx: TxParams,
+        pcc: &[u8],
+        pdc: &[u8],
+        rx_offset: u64,
+        rx: RxParams,
+    ) -> Result<Option<RecvResult<'_>>, MixedError> {
+        rx.check_single_shot()?;
+        let prepared = PreparedPcc::new(&tx, pcc)?;
+
+        RX_RING.reset();
+
+        super::discard_stale_events();
+        let tx_handle = super::new_handle();
+        let rx_handle = super::new_handle();
+        unsafe {
+            nrfxlib_sys::nrf_modem_dect_phy_tx_rx(&nrfxlib_sys::nrf_modem_dect_phy_tx_rx_params {
+                tx: tx.to_nrf(tx_handle, prepared.phy_type, prepared.as_bytes(), pdc),
+                // libmodem interprets the start time relative to the end of the transmission.
+                rx: rx.start_time(rx_offset).to_nrf(rx_handle),
+            })
+        }
+        .into_result()
+        .map_err(MixedError::General)?;
+
+        // When the transmission failed (e.g. because LBT found the channel busy), the receive
+        // operation may or may not report a completion. As it has a handle of its own, any
+        // completion is discarded by the next operation, so there is no need to wait for it.
+        match super::receive_event(&[tx_handle]).await {
+            DectEventOuter {
+                event: DectEvent::Completed(e),
+                ..
+            } => e.map_err(MixedError::from)?,
+            _ => panic!("Sequence violation"),
+        }
+
+        self.single_reception(rx_handle).await
+    }
+}
@@ -0,0 +1,186 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Operating bands and carrier numbering of Table 5.4.2-1 of ETSI TS 103 636-2 V2.1.1
+//!
+//! Carriers are numbered on a single 864 kHz raster that spans all bands; a band is a range of
+//! carrier numbers. Bands may overlap (e.g. [`BAND_2`] and [`BAND_22`]).
+//!
+//! FIXME: Only the rows of bands 1, 2, 4, 9 and 22 are transcribed so far. The other rows of the
+//! table are missing, and [`Band::from_number()`] returns `None` for them.
+
+use core::ops::RangeInclusive;
+
+/// Centre frequency of carrier 0, in kHz.
+const CARRIER_0_KHZ: u32 = 450_144;
+
+/// Spacing between carriers, in kHz.
+const CARRIER_SPACING_KHZ: u32 = 864;
+
+/// Regulatory restriction that applies to a band beyond what TS 103 636-2 expresses.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Restriction {
+    /// ETSI EN 301 406-2 only allows odd carriers when operating at 1.728 MHz bandwidth (β = 1),
+    /// which places them on the traditional DECT channel raster.
+    OddCarriersAt1728Khz,
+}
+
+/// An operating band.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Band {
+    number: u8,
+    first: u16,
+    last: u16,
+    group_index: u8,
+    restriction: Option<Restriction>,
+}
+
+/// Band 1: 1880 MHz to 1900 MHz (Europe).
+pub const BAND_1: Band = Band {
+    number: 1,
+    first: 1657,
+    last: 1677,
+    group_index: 0,
+    restriction: Some(Restriction::OddCarriersAt1728Khz),
+};
+
+/// Band 2: 1900 MHz to 1920 MHz.
+pub const BAND_2: Band = Band {
+    number: 2,
+    first: 1680,
+    last: 1700,
+    group_index: 0,
+    restriction: None,
+};
+
+/// Band 4: 902 MHz to 928 MHz.
+pub const BAND_4: Band = Band {
+    number: 4,
+    first: 524,
+    last: 552,
+    group_index: 1,
+    restriction: None,
+};
+
+/// Band 9: 1920 MHz to 1930 MHz.
+pub const BAND_9: Band = Band {
+    number: 9,
+    first: 1703,
+    last: 1711,
+    group_index: 0,
+    restriction: None,
+};
+
+/// Band 22: 1910 MHz to 1930 MHz.
+pub const BAND_22: Band = Band {
+    number: 22,
+    first: 1691,
+    last: 1711,
+    group_index: 0,
+    restriction: None,
+};
+
+/// All bands transcribed so far, ordered by band number.
+pub const BANDS: [Band; 5] = [BAND_1, BAND_2, BAND_4, BAND_9, BAND_22];
+
+impl Band {
+    /// Looks up a band by its number.
+    #[must_use]
+    pub fn from_number(number: u8) -> Option<Self> {
+        BANDS.into_iter().find(|band| band.number == number)
+    }
+
+    /// The band number.
+    #[must_use]
+    pub const fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// The carrier numbers of the band.
+    #[must_use]
+    pub const fn carriers(&self) -> RangeInclusive<u16> {
+        self.first..=self.last
+    }
+
+    /// Whether the carrier is in the band.
+    #[must_use]
+    pub const fn contains(&self, carrier: u16) -> bool {
+        self.first <= carrier && carrier <= self.last
+    }
+
+    /// Index of the group of bands that share a frequency range and thus a radio configuration.
+    ///
+    /// This is the value expected by the nRF91 modem's `band_group_index`: 0 for the bands
+    /// around 1.9 GHz, 1 for the band around 900 MHz.
+    #[must_use]
+    pub const fn band_group_index(&self) -> u8 {
+        self.group_index
+    }
+
+    /// Regulatory restriction that limits the usable carriers of the band, if any.
+    #[must_use]
+    pub const fn restriction(&self) -> Option<Restriction> {
+        self.restriction
+    }
+
+    /// Whether the carrier is in the band and usable at Fourier transform scaling factor `beta`
+    /// under the band's [restriction][Self::restriction()].
+    #[must_use]
+    pub const fn permits(&self, carrier: u16, beta: u8) -> bool {
+        if !self.contains(carrier) {
+            return false;
+        }
+        match self.restriction {
+            Some(Restriction::OddCarriersAt1728Khz) => beta != 1 || carrier % 2 == 1,
+            None => true,
+        }
+    }
+}
+
+/// Centre frequency of a carrier, in kHz.
+#[must_use]
+pub const fn centre_frequency_khz(carrier: u16) -> u32 {
+    CARRIER_0_KHZ + carrier as u32 * CARRIER_SPACING_KHZ
+}
+
+/// Carrier whose centre frequency (in kHz) is given.
+///
+/// Returns `None` if the frequency is not on the carrier raster.
+#[must_use]
+pub fn carrier_from_frequency_khz(frequency_khz: u32) -> Option<u16> {
+    let offset = frequency_khz.checked_sub(CARRIER_0_KHZ)?;
+    if !offset.is_multiple_of(CARRIER_SPACING_KHZ) {
+        return None;
+    }
+    u16::try_from(offset / CARRIER_SPACING_KHZ).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bands() {
+        assert_eq!(centre_frequency_khz(1657), 1_881_792);
+        assert_eq!(centre_frequency_khz(1677), 1_899_072);
+        assert_eq!(carrier_from_frequency_khz(1_881_792), Some(1657));
+        assert_eq!(carrier_from_frequency_khz(1_881_793), None);
+        assert_eq!(carrier_from_frequency_khz(400_000), None);
+
+        for band in BANDS {
+            assert_eq!(Band::from_number(band.number()), Some(band));
+            assert!(band.contains(*band.carriers().start()));
+            assert!(!band.contains(band.carriers().end() + 1));
+        }
+        assert_eq!(Band::from_number(3), None);
+        assert_eq!(BAND_4.band_group_index(), 1);
+
+        assert!(BAND_1.permits(1665, 1));
+        assert!(!BAND_1.permits(1666, 1));
+        assert!(BAND_1.permits(1666, 2));
+        assert!(BAND_2.permits(1690, 1));
+        assert!(!BAND_2.permits(1665, 1));
+    }
+}
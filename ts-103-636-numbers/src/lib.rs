@@ -15,6 +15,7 @@
 //! [DECT-2020 NR Endpoint Multiplexing Address Allocation]: https://portal.etsi.org/PNNS/Protocol-Specification-Allocation/DECT-2020-NR-Endpoint-Multiplexing-Addresses
 #![no_std]
 
+pub mod band;
 pub mod endpoint_multiplexing;
 
 pub mod mac_ie;
@@ -0,0 +1,90 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Onboarding exchange as in ETSI TS 103 636-1 Figure 7.2-2, without any radio
+//!
+//! An FT builds a beacon PDU that carries a Joining Beacon IE. A joining device answers with a
+//! unicast PDU carrying a Joining Information IE, which the FT then parses.
+//!
+//! Run with `cargo run --example joining`.
+
+use ts_103_636_numbers as numbers;
+use ts_103_636_utils::mac_ie::SerializeIe;
+use ts_103_636_utils::mac_ie::joining::{
+    JoiningBeacon, JoiningBeaconBuilder, JoiningInformation, JoiningInformationBuilder,
+};
+use ts_103_636_utils::mac_pdu::{
+    Header, OwnedBeacon, OwnedHeader, OwnedMacCommonHeader, OwnedUnicast,
+};
+
+use numbers::endpoint_multiplexing::{CONFIGURATION_DATA_REQUEST, CONFIGURATION_DATA_RESPONSE};
+
+const NETWORK_ID: u32 = 0x00ab_cdef;
+const FT_ADDRESS: u32 = 0x1000_0001;
+const DEVICE_ADDRESS: u32 = 0x2000_0002;
+
+/// Builds a PDU from a header and one IE into the buffer, returning the written part.
+fn build<'a>(buffer: &'a mut [u8], header: &OwnedHeader, ie: &impl SerializeIe) -> &'a [u8] {
+    let mut cursor = &mut buffer[..];
+    header.serialize(&mut cursor).unwrap();
+    ie.serialize(&mut cursor).unwrap();
+    let remaining = cursor.len();
+    &buffer[..buffer.len() - remaining]
+}
+
+fn main() {
+    // The FT advertises that it accepts joining devices, along with its public key and the EPs
+    // through which configuration happens.
+    let ft_key = [0x42; 32];
+    let ft_endpoints = [CONFIGURATION_DATA_REQUEST, CONFIGURATION_DATA_RESPONSE];
+    let header = OwnedHeader::new(OwnedMacCommonHeader::Beacon(
+        OwnedBeacon::new(NETWORK_ID, FT_ADDRESS).unwrap(),
+    ));
+    let joining_beacon = JoiningBeaconBuilder::new(&ft_endpoints)
+        .unwrap()
+        .public_key(&ft_key)
+        .unwrap();
+    let mut beacon_buffer = [0; 64];
+    let beacon_pdu = build(&mut beacon_buffer, &header, &joining_beacon);
+    println!("Joining beacon PDU: {beacon_pdu:02x?}");
+
+    // The joining device finds the beacon, and answers with its own key and the EPs it uses.
+    let beacon = Header::parse(beacon_pdu).unwrap();
+    let ie = beacon
+        .tail_items()
+        .map(Result::unwrap)
+        .find(|ie| ie.ie_number() == numbers::mac_ie::ie6bit::JOINING_BEACON)
+        .expect("Beacon contains a Joining Beacon IE");
+    let joining_beacon = JoiningBeacon::parse(ie.payload()).unwrap();
+    println!(
+        "Device sees FT key {:02x?} with EPs {:?}",
+        joining_beacon.public_key(),
+        joining_beacon.endpoints().collect::<Vec<_>>(),
+    );
+
+    let device_key = [0x17; 32];
+    let device_endpoints = [CONFIGURATION_DATA_REQUEST];
+    let header = OwnedHeader::new(OwnedMacCommonHeader::Unicast(
+        OwnedUnicast::new(false, 0, FT_ADDRESS, DEVICE_ADDRESS).unwrap(),
+    ));
+    let joining_information = JoiningInformationBuilder::new(&device_endpoints)
+        .unwrap()
+        .public_key(&device_key)
+        .unwrap();
+    let mut information_buffer = [0; 64];
+    let information_pdu = build(&mut information_buffer, &header, &joining_information);
+    println!("Joining information PDU: {information_pdu:02x?}");
+
+    // The FT parses the response.
+    let information = Header::parse(information_pdu).unwrap();
+    let ie = information
+        .tail_items()
+        .map(Result::unwrap)
+        .find(|ie| ie.ie_number() == numbers::mac_ie::ie6bit::JOINING_INFORMATION)
+        .expect("Response contains a Joining Information IE");
+    let joining_information = JoiningInformation::parse(ie.payload()).unwrap();
+    println!(
+        "FT sees device key {:02x?} with EPs {:?}",
+        joining_information.public_key(),
+        joining_information.endpoints().collect::<Vec<_>>(),
+    );
+}
@@ -7,6 +7,11 @@
 
 pub mod mac_ie;
 pub mod mac_pdu;
+pub mod pcc;
+#[cfg(feature = "security")]
+pub mod security;
+pub mod timing;
+pub mod transport_block;
 
 /// Something in the input data structure violated this crate's expectation of what specification
 /// compliant input should look like.
@@ -20,3 +25,8 @@ pub struct ParsingError;
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct InputLengthError;
+
+/// A value passed in for serialization exceeds what the field it is serialized into can express.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ValueRangeError;
@@ -0,0 +1,791 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Association Request, Association Response and Association Release messages
+//!
+//! These are the payloads of the
+//! [`ASSOCIATION_REQUEST`][numbers::mac_ie::ie6bit::ASSOCIATION_REQUEST],
+//! [`ASSOCIATION_RESPONSE`][numbers::mac_ie::ie6bit::ASSOCIATION_RESPONSE] and
+//! [`ASSOCIATION_RELEASE`][numbers::mac_ie::ie6bit::ASSOCIATION_RELEASE] IEs as defined in
+//! Sections 6.4.2.4 to 6.4.2.6 of ETSI TS 103 636-4 V2.1.1.
+//!
+//! Causes are exposed as enums; parsing fails on their reserved values. HARQ parameters and the
+//! reject timer are exposed in their coded form.
+
+use super::beacon::channel;
+use super::{AnyIeType, SerializeIe, flag};
+use crate::{ParsingError, ValueRangeError};
+use ts_103_636_numbers as numbers;
+
+coded_enum! {
+    /// Setup Cause of an Association Request.
+    pub enum SetupCause {
+        /// Initial association.
+        InitialAssociation = 0,
+        /// Association to request a new set of flows.
+        NewFlows = 1,
+        /// Association due to mobility.
+        Mobility = 2,
+        /// Re-association after an error (e.g. loss of connection or security).
+        Reassociation = 3,
+        /// Change of the operating channel of this FT.
+        ChangeOperatingChannel = 4,
+        /// Change of the operating mode (PT or FT).
+        ChangeOperatingMode = 5,
+        /// Response to paging.
+        PagingResponse = 6,
+    }
+}
+
+coded_enum! {
+    /// Reject Cause of an Association Response.
+    pub enum RejectCause {
+        /// Not sufficient radio capacity.
+        NoRadioCapacity = 0,
+        /// Not sufficient hardware capacity.
+        NoHardwareCapacity = 1,
+        /// Conflict with the requesting device's short RD ID was detected.
+        ConflictingShortId = 2,
+        /// Non-secured association requests are not supported.
+        SecurityRequired = 3,
+        /// Other reason.
+        Other = 4,
+    }
+}
+
+coded_enum! {
+    /// Release Cause of an Association Release.
+    pub enum ReleaseCause {
+        /// Connection termination.
+        ConnectionTermination = 0,
+        /// Mobility.
+        Mobility = 1,
+        /// Long inactivity.
+        LongInactivity = 2,
+        /// Previous association was not successful or used an incompatible configuration.
+        IncompatibleConfiguration = 3,
+        /// Not sufficient hardware or memory resources.
+        NoHardwareResources = 4,
+        /// Not sufficient radio resources.
+        NoRadioResources = 5,
+        /// Bad radio quality.
+        BadRadioQuality = 6,
+        /// Security error.
+        SecurityError = 7,
+        /// A short RD ID conflict was detected on the PT side.
+        ConflictingShortId = 8,
+        /// The receiver is not associated with the sender.
+        NotAssociated = 9,
+    }
+}
+
+/// Converts a coded Reject Timer into seconds.
+///
+/// Returns `None` for reserved values.
+#[must_use]
+pub fn reject_timer_s(code: u8) -> Option<u32> {
+    const TABLE: [u32; 9] = [0, 5, 10, 30, 60, 120, 180, 300, 600];
+    TABLE.get(usize::from(code)).copied()
+}
+
+/// Maximum number of flows listed in an Association Request or Response.
+const MAX_FLOWS: usize = 6;
+
+/// Number of Flows value by which an Association Response accepts all requested flows.
+const ALL_FLOWS: u8 = 7;
+
+/// Length of the [`FtModeParameters`] when serialized.
+const FT_MODE_LEN: usize = 7;
+
+/// HARQ configuration for one direction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HarqParameters {
+    /// The coded 3-bit number of HARQ processes.
+    pub processes: u8,
+    /// The coded 5-bit maximum HARQ re-transmission delay.
+    pub max_retransmission_delay: u8,
+}
+
+impl HarqParameters {
+    fn from_byte(byte: u8) -> Self {
+        Self {
+            processes: byte >> 5,
+            max_retransmission_delay: byte & 0x1f,
+        }
+    }
+
+    fn to_byte(self) -> Result<u8, ValueRangeError> {
+        if self.processes > 0x07 || self.max_retransmission_delay > 0x1f {
+            return Err(ValueRangeError);
+        }
+        Ok((self.processes << 5) | self.max_retransmission_delay)
+    }
+}
+
+/// Parameters that a device operating in FT mode announces in its Association Request.
+///
+/// They have the same meaning as the equally named fields of a
+/// [`NetworkBeacon`][super::beacon::NetworkBeacon].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FtModeParameters {
+    /// The coded Network Beacon Period.
+    pub network_beacon_period: u8,
+    /// The coded Cluster Beacon Period.
+    pub cluster_beacon_period: u8,
+    /// The channel on which the next cluster beacon is sent.
+    pub next_cluster_channel: u16,
+    /// Time to the next cluster beacon, in µs.
+    pub time_to_next: u32,
+}
+
+impl FtModeParameters {
+    fn parse(data: &[u8]) -> Self {
+        Self {
+            network_beacon_period: data[0] >> 4,
+            cluster_beacon_period: data[0] & 0x0f,
+            next_cluster_channel: channel(&data[1..3]),
+            time_to_next: u32::from_be_bytes([data[3], data[4], data[5], data[6]]),
+        }
+    }
+
+    fn check(self) -> Result<(), ValueRangeError> {
+        if self.network_beacon_period > 0x0f
+            || self.cluster_beacon_period > 0x0f
+            || self.next_cluster_channel > 0x1fff
+        {
+            return Err(ValueRangeError);
+        }
+        Ok(())
+    }
+
+    fn serialize<W: embedded_io::Write>(self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[(self.network_beacon_period << 4) | self.cluster_beacon_period])?;
+        w.write_all(&self.next_cluster_channel.to_be_bytes())?;
+        w.write_all(&self.time_to_next.to_be_bytes())
+    }
+}
+
+/// A list of up to 6 flow IDs, as used in the builders.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Flows {
+    ids: [u8; MAX_FLOWS],
+    count: u8,
+}
+
+impl Flows {
+    fn push(&mut self, flow_id: u8) -> Result<(), ValueRangeError> {
+        let slot = self
+            .ids
+            .get_mut(usize::from(self.count))
+            .ok_or(ValueRangeError)?;
+        if flow_id > 0x3f {
+            return Err(ValueRangeError);
+        }
+        *slot = flow_id;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.ids[..usize::from(self.count)]
+    }
+}
+
+/// Length of an Association Request payload as indicated by its leading flags.
+///
+/// Returns `None` if the data is too short to tell.
+fn association_request_length(data: &[u8]) -> Option<usize> {
+    let flows = usize::from((data.first()? >> 2) & 0x07);
+    let current = flag(*data.get(1)?, 7);
+    Some(4 + flows + FT_MODE_LEN * flag(data[0], 0) + 2 * current)
+}
+
+/// Length of an Association Response payload as indicated by its leading flags.
+///
+/// Returns `None` if the data is too short to tell.
+fn association_response_length(data: &[u8]) -> Option<usize> {
+    let flags = *data.first()?;
+    let flows = match (flags >> 2) & 0x07 {
+        ALL_FLOWS => 0,
+        n => usize::from(n),
+    };
+    Some(1 + (1 - flag(flags, 7)) + 2 * flag(flags, 5) + flows + 2 * flag(flags, 1))
+}
+
+/// View on an Association Request message.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AssociationRequest<'buf>(&'buf [u8]);
+
+impl<'buf> AssociationRequest<'buf> {
+    /// Interprets the payload of an Association Request IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the one announced by its flags, or if the
+    /// setup cause or number of flows have reserved values.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if association_request_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        SetupCause::from_code(payload[0] >> 5).ok_or(ParsingError)?;
+        if usize::from((payload[0] >> 2) & 0x07) > MAX_FLOWS {
+            return Err(ParsingError);
+        }
+        Ok(Self(payload))
+    }
+
+    fn flows_len(&self) -> usize {
+        usize::from((self.0[0] >> 2) & 0x07)
+    }
+
+    /// The reason for the association.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "Reserved values were excluded at parsing"
+    )]
+    #[must_use]
+    pub fn setup_cause(&self) -> SetupCause {
+        SetupCause::from_code(self.0[0] >> 5).expect("Checked at parsing")
+    }
+
+    /// The Power Const flag: Whether the device operates under power constraints.
+    #[must_use]
+    pub fn power_constraints(&self) -> bool {
+        flag(self.0[0], 1) != 0
+    }
+
+    /// The HARQ configuration for transmissions by the requesting device.
+    #[must_use]
+    pub fn harq_tx(&self) -> HarqParameters {
+        HarqParameters::from_byte(self.0[2])
+    }
+
+    /// The HARQ configuration for receptions by the requesting device.
+    #[must_use]
+    pub fn harq_rx(&self) -> HarqParameters {
+        HarqParameters::from_byte(self.0[3])
+    }
+
+    /// The 6-bit IDs of the flows the device requests.
+    pub fn flow_ids(&self) -> impl Iterator<Item = u8> + use<'buf> {
+        self.0[4..4 + self.flows_len()]
+            .iter()
+            .map(|byte| byte & 0x3f)
+    }
+
+    /// The parameters of the requesting device's own cluster, if it operates in FT mode.
+    #[must_use]
+    pub fn ft_mode(&self) -> Option<FtModeParameters> {
+        let offset = 4 + self.flows_len();
+        (flag(self.0[0], 0) != 0).then(|| FtModeParameters::parse(&self.0[offset..]))
+    }
+
+    /// The channel of the requesting device's own cluster, if it is not the next cluster channel.
+    #[must_use]
+    pub fn current_cluster_channel(&self) -> Option<u16> {
+        let offset = 4 + self.flows_len() + FT_MODE_LEN * flag(self.0[0], 0);
+        (flag(self.0[1], 7) != 0).then(|| channel(&self.0[offset..]))
+    }
+}
+
+/// Builder for an Association Request message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AssociationRequestBuilder {
+    setup_cause: SetupCause,
+    power_constraints: bool,
+    harq_tx: u8,
+    harq_rx: u8,
+    flows: Flows,
+    ft_mode: Option<FtModeParameters>,
+    current_cluster_channel: Option<u16>,
+}
+
+impl AssociationRequestBuilder {
+    /// Creates an Association Request without flows.
+    ///
+    /// # Errors
+    ///
+    /// This errs if any of the HARQ parameters exceeds its field.
+    pub fn new(
+        setup_cause: SetupCause,
+        harq_tx: HarqParameters,
+        harq_rx: HarqParameters,
+    ) -> Result<Self, ValueRangeError> {
+        Ok(Self {
+            setup_cause,
+            power_constraints: false,
+            harq_tx: harq_tx.to_byte()?,
+            harq_rx: harq_rx.to_byte()?,
+            flows: Flows::default(),
+            ft_mode: None,
+            current_cluster_channel: None,
+        })
+    }
+
+    /// Sets the Power Const flag.
+    #[must_use]
+    pub fn power_constraints(mut self, power_constraints: bool) -> Self {
+        self.power_constraints = power_constraints;
+        self
+    }
+
+    /// Adds a flow to the request.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the flow ID exceeds 6 bits, or if 6 flows have already been added.
+    pub fn flow(mut self, flow_id: u8) -> Result<Self, ValueRangeError> {
+        self.flows.push(flow_id)?;
+        Ok(self)
+    }
+
+    /// Indicates that the device operates in FT mode, with the given parameters.
+    ///
+    /// # Errors
+    ///
+    /// This errs if a period code exceeds 4 bits or the channel exceeds 13 bits.
+    pub fn ft_mode(mut self, parameters: FtModeParameters) -> Result<Self, ValueRangeError> {
+        parameters.check()?;
+        self.ft_mode = Some(parameters);
+        Ok(self)
+    }
+
+    /// Sets the channel of the requesting device's own cluster.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the channel exceeds 13 bits.
+    pub fn current_cluster_channel(mut self, channel: u16) -> Result<Self, ValueRangeError> {
+        if channel > 0x1fff {
+            return Err(ValueRangeError);
+        }
+        self.current_cluster_channel = Some(channel);
+        Ok(self)
+    }
+}
+
+impl SerializeIe for AssociationRequestBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::ASSOCIATION_REQUEST);
+
+    fn payload_len(&self) -> usize {
+        4 + usize::from(self.flows.count)
+            + FT_MODE_LEN * usize::from(self.ft_mode.is_some())
+            + 2 * usize::from(self.current_cluster_channel.is_some())
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[
+            (self.setup_cause.code() << 5)
+                | (self.flows.count << 2)
+                | (u8::from(self.power_constraints) << 1)
+                | u8::from(self.ft_mode.is_some()),
+            u8::from(self.current_cluster_channel.is_some()) << 7,
+            self.harq_tx,
+            self.harq_rx,
+        ])?;
+        w.write_all(self.flows.as_slice())?;
+        if let Some(ft_mode) = self.ft_mode {
+            ft_mode.serialize(w)?;
+        }
+        if let Some(channel) = self.current_cluster_channel {
+            w.write_all(&channel.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// View on an Association Response message.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AssociationResponse<'buf>(&'buf [u8]);
+
+impl<'buf> AssociationResponse<'buf> {
+    /// Interprets the payload of an Association Response IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the one announced by its flags, or if the
+    /// reject cause has a reserved value.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if association_response_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        if flag(payload[0], 7) == 0 {
+            RejectCause::from_code(payload[1] >> 4).ok_or(ParsingError)?;
+        }
+        Ok(Self(payload))
+    }
+
+    fn flags(&self) -> u8 {
+        self.0[0]
+    }
+
+    fn harq_offset(&self) -> usize {
+        1 + (1 - flag(self.flags(), 7))
+    }
+
+    fn flows_offset(&self) -> usize {
+        self.harq_offset() + 2 * flag(self.flags(), 5)
+    }
+
+    fn flows_len(&self) -> usize {
+        match (self.flags() >> 2) & 0x07 {
+            ALL_FLOWS => 0,
+            n => usize::from(n),
+        }
+    }
+
+    /// The ACK/NACK field: Whether the association was accepted.
+    #[must_use]
+    pub fn accepted(&self) -> bool {
+        flag(self.flags(), 7) != 0
+    }
+
+    /// The reason for rejecting the association, if it was rejected.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "Reserved values were excluded at parsing"
+    )]
+    #[must_use]
+    pub fn reject_cause(&self) -> Option<RejectCause> {
+        (!self.accepted())
+            .then(|| RejectCause::from_code(self.0[1] >> 4).expect("Checked at parsing"))
+    }
+
+    /// The coded Reject Timer (see [`reject_timer_s()`]), if the association was rejected.
+    #[must_use]
+    pub fn reject_timer(&self) -> Option<u8> {
+        (!self.accepted()).then(|| self.0[1] & 0x0f)
+    }
+
+    /// The HARQ configuration for receptions by the responding device, if it differs from the
+    /// requested one.
+    #[must_use]
+    pub fn harq_rx(&self) -> Option<HarqParameters> {
+        (flag(self.flags(), 5) != 0).then(|| HarqParameters::from_byte(self.0[self.harq_offset()]))
+    }
+
+    /// The HARQ configuration for transmissions by the responding device, if it differs from
+    /// the requested one.
+    #[must_use]
+    pub fn harq_tx(&self) -> Option<HarqParameters> {
+        (flag(self.flags(), 5) != 0)
+            .then(|| HarqParameters::from_byte(self.0[self.harq_offset() + 1]))
+    }
+
+    /// The 6-bit IDs of the accepted flows.
+    ///
+    /// This is `None` if all flows of the request were accepted.
+    #[must_use]
+    pub fn flow_ids(&self) -> Option<impl Iterator<Item = u8> + use<'buf>> {
+        let offset = self.flows_offset();
+        let flows = &self.0[offset..offset + self.flows_len()];
+        ((self.flags() >> 2) & 0x07 != ALL_FLOWS).then(|| flows.iter().map(|byte| byte & 0x3f))
+    }
+
+    /// The 7-bit Group ID and Resource Tag, if the device was assigned to a group.
+    #[must_use]
+    pub fn group(&self) -> Option<(u8, u8)> {
+        let offset = self.flows_offset() + self.flows_len();
+        (flag(self.flags(), 1) != 0).then(|| (self.0[offset] & 0x7f, self.0[offset + 1] & 0x7f))
+    }
+}
+
+/// Builder for an Association Response message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AssociationResponseBuilder {
+    reject: Option<(RejectCause, u8)>,
+    harq: Option<(u8, u8)>,
+    all_flows: bool,
+    flows: Flows,
+    group: Option<(u8, u8)>,
+}
+
+impl AssociationResponseBuilder {
+    /// Creates a response that accepts the association with all requested flows.
+    ///
+    /// Adding flows restricts the accepted flows to those.
+    #[must_use]
+    pub fn accept() -> Self {
+        Self {
+            reject: None,
+            harq: None,
+            all_flows: true,
+            flows: Flows::default(),
+            group: None,
+        }
+    }
+
+    /// Creates a response that rejects the association.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the coded reject timer exceeds 4 bits.
+    pub fn reject(cause: RejectCause, reject_timer: u8) -> Result<Self, ValueRangeError> {
+        if reject_timer > 0x0f {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            reject: Some((cause, reject_timer)),
+            all_flows: false,
+            ..Self::accept()
+        })
+    }
+
+    /// Sets a HARQ configuration that differs from the requested one.
+    ///
+    /// # Errors
+    ///
+    /// This errs if any of the HARQ parameters exceeds its field.
+    pub fn harq(
+        mut self,
+        harq_rx: HarqParameters,
+        harq_tx: HarqParameters,
+    ) -> Result<Self, ValueRangeError> {
+        self.harq = Some((harq_rx.to_byte()?, harq_tx.to_byte()?));
+        Ok(self)
+    }
+
+    /// Adds an accepted flow.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the flow ID exceeds 6 bits, or if 6 flows have already been added.
+    pub fn flow(mut self, flow_id: u8) -> Result<Self, ValueRangeError> {
+        self.flows.push(flow_id)?;
+        self.all_flows = false;
+        Ok(self)
+    }
+
+    /// Assigns the device to a group.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the group ID or resource tag exceed 7 bits.
+    pub fn group(mut self, group_id: u8, resource_tag: u8) -> Result<Self, ValueRangeError> {
+        if group_id > 0x7f || resource_tag > 0x7f {
+            return Err(ValueRangeError);
+        }
+        self.group = Some((group_id, resource_tag));
+        Ok(self)
+    }
+}
+
+impl SerializeIe for AssociationResponseBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::ASSOCIATION_RESPONSE);
+
+    fn payload_len(&self) -> usize {
+        1 + usize::from(self.reject.is_some())
+            + 2 * usize::from(self.harq.is_some())
+            + usize::from(self.flows.count)
+            + 2 * usize::from(self.group.is_some())
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        let flows = if self.all_flows {
+            ALL_FLOWS
+        } else {
+            self.flows.count
+        };
+        w.write_all(&[(u8::from(self.reject.is_none()) << 7)
+            | (u8::from(self.harq.is_some()) << 5)
+            | (flows << 2)
+            | (u8::from(self.group.is_some()) << 1)])?;
+        if let Some((cause, timer)) = self.reject {
+            w.write_all(&[(cause.code() << 4) | timer])?;
+        }
+        if let Some((rx, tx)) = self.harq {
+            w.write_all(&[rx, tx])?;
+        }
+        w.write_all(self.flows.as_slice())?;
+        if let Some((group_id, resource_tag)) = self.group {
+            w.write_all(&[group_id, resource_tag])?;
+        }
+        Ok(())
+    }
+}
+
+/// View on an Association Release message.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AssociationRelease<'buf>(&'buf [u8; 1]);
+
+impl<'buf> AssociationRelease<'buf> {
+    /// Interprets the payload of an Association Release IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload is not 1 byte long, or if the release cause has a reserved value.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        let payload: &[u8; 1] = payload.try_into().map_err(|_| ParsingError)?;
+        ReleaseCause::from_code(payload[0] >> 4).ok_or(ParsingError)?;
+        Ok(Self(payload))
+    }
+
+    /// The reason for releasing the association.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "Reserved values were excluded at parsing"
+    )]
+    #[must_use]
+    pub fn release_cause(&self) -> ReleaseCause {
+        ReleaseCause::from_code(self.0[0] >> 4).expect("Checked at parsing")
+    }
+}
+
+/// Builder for an Association Release message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AssociationReleaseBuilder {
+    release_cause: ReleaseCause,
+}
+
+impl AssociationReleaseBuilder {
+    /// Creates an Association Release with the given cause.
+    #[must_use]
+    pub fn new(release_cause: ReleaseCause) -> Self {
+        Self { release_cause }
+    }
+}
+
+impl SerializeIe for AssociationReleaseBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::ASSOCIATION_RELEASE);
+
+    fn payload_len(&self) -> usize {
+        1
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[self.release_cause.code() << 4])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        let harq = HarqParameters {
+            processes: 2,
+            max_retransmission_delay: 10,
+        };
+        let builder = AssociationRequestBuilder::new(SetupCause::InitialAssociation, harq, harq)
+            .unwrap()
+            .flow(1)
+            .unwrap()
+            .flow(4)
+            .unwrap();
+        let mut written = [0; 8];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(written, [0x40 | 0x0a, 6, 0x08, 0x00, 0x4a, 0x4a, 1, 4]);
+
+        let request = AssociationRequest::parse(&written[2..]).unwrap();
+        assert_eq!(request.setup_cause(), SetupCause::InitialAssociation);
+        assert!(!request.power_constraints());
+        assert_eq!(request.harq_tx(), harq);
+        assert_eq!(request.harq_rx(), harq);
+        assert!(request.flow_ids().eq([1, 4]));
+        assert_eq!(request.ft_mode(), None);
+        assert_eq!(request.current_cluster_channel(), None);
+
+        let ft_mode = FtModeParameters {
+            network_beacon_period: 3,
+            cluster_beacon_period: 4,
+            next_cluster_channel: 1667,
+            time_to_next: 100_000,
+        };
+        let builder = AssociationRequestBuilder::new(SetupCause::Mobility, harq, harq)
+            .unwrap()
+            .power_constraints(true)
+            .flow(1)
+            .unwrap()
+            .ft_mode(ft_mode)
+            .unwrap()
+            .current_cluster_channel(1669)
+            .unwrap();
+        let mut written = [0; 14];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        assert_eq!(builder.payload_len(), 14);
+        let request = AssociationRequest::parse(&written).unwrap();
+        assert_eq!(request.setup_cause(), SetupCause::Mobility);
+        assert!(request.power_constraints());
+        assert!(request.flow_ids().eq([1]));
+        assert_eq!(request.ft_mode(), Some(ft_mode));
+        assert_eq!(request.current_cluster_channel(), Some(1669));
+
+        AssociationRequest::parse(&written[..13]).unwrap_err();
+        // Reserved setup cause
+        AssociationRequest::parse(&[0xe0, 0, 0, 0]).unwrap_err();
+    }
+
+    #[test]
+    fn test_response() {
+        let builder = AssociationResponseBuilder::accept();
+        let mut written = [0; 3];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(written, [0x40 | 0x0b, 1, 0x9c]);
+        let response = AssociationResponse::parse(&written[2..]).unwrap();
+        assert!(response.accepted());
+        assert_eq!(response.reject_cause(), None);
+        assert!(response.flow_ids().is_none());
+        assert_eq!(response.harq_rx(), None);
+        assert_eq!(response.group(), None);
+
+        let harq_rx = HarqParameters {
+            processes: 1,
+            max_retransmission_delay: 3,
+        };
+        let harq_tx = HarqParameters {
+            processes: 4,
+            max_retransmission_delay: 31,
+        };
+        let builder = AssociationResponseBuilder::accept()
+            .harq(harq_rx, harq_tx)
+            .unwrap()
+            .flow(1)
+            .unwrap()
+            .group(5, 9)
+            .unwrap();
+        let mut written = [0; 6];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        assert_eq!(written, [0xa6, 0x23, 0x9f, 1, 5, 9]);
+        let response = AssociationResponse::parse(&written).unwrap();
+        assert_eq!(response.harq_rx(), Some(harq_rx));
+        assert_eq!(response.harq_tx(), Some(harq_tx));
+        assert!(response.flow_ids().unwrap().eq([1]));
+        assert_eq!(response.group(), Some((5, 9)));
+
+        let builder = AssociationResponseBuilder::reject(RejectCause::NoRadioCapacity, 3).unwrap();
+        let mut written = [0; 2];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        assert_eq!(written, [0x00, 0x03]);
+        let response = AssociationResponse::parse(&written).unwrap();
+        assert!(!response.accepted());
+        assert_eq!(response.reject_cause(), Some(RejectCause::NoRadioCapacity));
+        assert_eq!(response.reject_timer().and_then(reject_timer_s), Some(30));
+        assert!(response.flow_ids().unwrap().eq([]));
+
+        // Reserved reject cause
+        AssociationResponse::parse(&[0x00, 0xf0]).unwrap_err();
+        AssociationResponse::parse(&[0x00]).unwrap_err();
+    }
+
+    #[test]
+    fn test_release() {
+        let mut written = [0; 3];
+        AssociationReleaseBuilder::new(ReleaseCause::LongInactivity)
+            .serialize(&mut &mut written[..])
+            .unwrap();
+        assert_eq!(written, [0x40 | 0x0c, 1, 0x20]);
+        let release = AssociationRelease::parse(&written[2..]).unwrap();
+        assert_eq!(release.release_cause(), ReleaseCause::LongInactivity);
+        AssociationRelease::parse(&[0xf0]).unwrap_err();
+        AssociationRelease::parse(&[0x20, 0]).unwrap_err();
+    }
+}
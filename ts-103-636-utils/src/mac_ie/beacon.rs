@@ -0,0 +1,652 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Network Beacon and Cluster Beacon messages
+//!
+//! These are the payloads of the [`NETWORK_BEACON`][numbers::mac_ie::ie6bit::NETWORK_BEACON] and
+//! [`CLUSTER_BEACON`][numbers::mac_ie::ie6bit::CLUSTER_BEACON] IEs as defined in Sections 6.4.2.2
+//! and 6.4.2.3 of ETSI TS 103 636-4 V2.1.1.
+//!
+//! Fields that carry coded values (powers, periods, qualities) are exposed in their coded form;
+//! free functions in this module convert them into physical units.
+
+use super::{AnyIeType, SerializeIe, flag};
+use crate::{ParsingError, ValueRangeError};
+use ts_103_636_numbers as numbers;
+
+/// Converts a Network Beacon Period code into milliseconds.
+///
+/// Returns `None` for reserved values.
+#[must_use]
+pub fn network_beacon_period_ms(code: u8) -> Option<u32> {
+    const TABLE: [u32; 7] = [50, 100, 500, 1000, 1500, 2000, 4000];
+    TABLE.get(usize::from(code)).copied()
+}
+
+/// Converts a Cluster Beacon Period code into milliseconds.
+///
+/// Returns `None` for reserved values.
+#[must_use]
+pub fn cluster_beacon_period_ms(code: u8) -> Option<u32> {
+    const TABLE: [u32; 11] = [10, 50, 100, 500, 1000, 1500, 2000, 4000, 8000, 16000, 32000];
+    TABLE.get(usize::from(code)).copied()
+}
+
+/// Converts a 2-bit Relative Quality or Minimum Quality code into dB.
+#[must_use]
+pub fn quality_db(code: u8) -> u8 {
+    (code & 0x03) * 3
+}
+
+/// Reads a 13-bit channel number from 2 bytes whose upper 3 bits are reserved.
+pub(super) fn channel(data: &[u8]) -> u16 {
+    u16::from_be_bytes([data[0], data[1]]) & 0x1fff
+}
+
+/// Length of the Network Beacon fields that are always present.
+const NETWORK_BEACON_FIXED_LEN: usize = 8;
+
+/// Length of a Network Beacon payload as indicated by its leading flags.
+///
+/// Returns `None` if the data is too short to tell.
+pub(super) fn network_beacon_length(data: &[u8]) -> Option<usize> {
+    let flags = *data.first()?;
+    Some(
+        NETWORK_BEACON_FIXED_LEN
+            + flag(flags, 4)
+            + 2 * flag(flags, 2)
+            + 2 * usize::from(flags & 0x03),
+    )
+}
+
+/// Length of a Cluster Beacon payload as indicated by its leading flags.
+///
+/// Returns `None` if the data is too short to tell.
+pub(super) fn cluster_beacon_length(data: &[u8]) -> Option<usize> {
+    let flags = *data.get(1)?;
+    Some(4 + flag(flags, 4) + flag(flags, 2) + 2 * flag(flags, 1) + 4 * flag(flags, 0))
+}
+
+/// View on a Network Beacon message.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NetworkBeacon<'buf>(&'buf [u8]);
+
+impl<'buf> NetworkBeacon<'buf> {
+    /// Interprets the payload of a Network Beacon IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the one announced by its flags.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if network_beacon_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        Ok(Self(payload))
+    }
+
+    /// The coded Clusters Max TX Power, if present.
+    ///
+    /// See [`crate::pcc::transmit_power_dbm()`] for its meaning.
+    #[must_use]
+    pub fn clusters_max_tx_power(&self) -> Option<u8> {
+        (flag(self.0[0], 4) != 0).then(|| self.0[NETWORK_BEACON_FIXED_LEN] & 0x0f)
+    }
+
+    /// The Power Const flag: Whether the FT operates under power constraints.
+    #[must_use]
+    pub fn power_constraints(&self) -> bool {
+        flag(self.0[0], 3) != 0
+    }
+
+    /// The coded Network Beacon Period; see [`network_beacon_period_ms()`].
+    #[must_use]
+    pub fn network_beacon_period(&self) -> u8 {
+        self.0[1] >> 4
+    }
+
+    /// The coded Cluster Beacon Period; see [`cluster_beacon_period_ms()`].
+    #[must_use]
+    pub fn cluster_beacon_period(&self) -> u8 {
+        self.0[1] & 0x0f
+    }
+
+    /// The channel on which the next cluster beacon is sent.
+    #[must_use]
+    pub fn next_cluster_channel(&self) -> u16 {
+        channel(&self.0[2..4])
+    }
+
+    /// Time to the next cluster beacon, in µs.
+    #[must_use]
+    pub fn time_to_next(&self) -> u32 {
+        u32::from_be_bytes([self.0[4], self.0[5], self.0[6], self.0[7]])
+    }
+
+    /// The current cluster channel, if it is different from the network beacon's channel.
+    #[must_use]
+    pub fn current_cluster_channel(&self) -> Option<u16> {
+        let offset = NETWORK_BEACON_FIXED_LEN + flag(self.0[0], 4);
+        (flag(self.0[0], 2) != 0).then(|| channel(&self.0[offset..]))
+    }
+
+    /// Additional channels on which network beacons are sent (up to 3).
+    pub fn additional_network_beacon_channels(&self) -> impl Iterator<Item = u16> + use<'buf> {
+        let offset = NETWORK_BEACON_FIXED_LEN + flag(self.0[0], 4) + 2 * flag(self.0[0], 2);
+        self.0[offset..].chunks_exact(2).map(channel)
+    }
+}
+
+/// Builder for a Network Beacon message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NetworkBeaconBuilder {
+    clusters_max_tx_power: Option<u8>,
+    power_constraints: bool,
+    network_beacon_period: u8,
+    cluster_beacon_period: u8,
+    next_cluster_channel: u16,
+    time_to_next: u32,
+    current_cluster_channel: Option<u16>,
+    additional_channels: [u16; 3],
+    additional_channels_count: u8,
+}
+
+impl NetworkBeaconBuilder {
+    /// Creates a Network Beacon with its mandatory fields.
+    ///
+    /// The periods are given as codes (see [`network_beacon_period_ms()`] and
+    /// [`cluster_beacon_period_ms()`]), `time_to_next` is in µs.
+    ///
+    /// # Errors
+    ///
+    /// This errs if a period code exceeds 4 bits or the channel exceeds 13 bits.
+    pub fn new(
+        network_beacon_period: u8,
+        cluster_beacon_period: u8,
+        next_cluster_channel: u16,
+        time_to_next: u32,
+    ) -> Result<Self, ValueRangeError> {
+        if network_beacon_period > 0x0f
+            || cluster_beacon_period > 0x0f
+            || next_cluster_channel > 0x1fff
+        {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            clusters_max_tx_power: None,
+            power_constraints: false,
+            network_beacon_period,
+            cluster_beacon_period,
+            next_cluster_channel,
+            time_to_next,
+            current_cluster_channel: None,
+            additional_channels: [0; 3],
+            additional_channels_count: 0,
+        })
+    }
+
+    /// Sets the coded Clusters Max TX Power.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the code exceeds 4 bits.
+    pub fn clusters_max_tx_power(mut self, code: u8) -> Result<Self, ValueRangeError> {
+        if code > 0x0f {
+            return Err(ValueRangeError);
+        }
+        self.clusters_max_tx_power = Some(code);
+        Ok(self)
+    }
+
+    /// Sets the Power Const flag.
+    #[must_use]
+    pub fn power_constraints(mut self, power_constraints: bool) -> Self {
+        self.power_constraints = power_constraints;
+        self
+    }
+
+    /// Sets the current cluster channel.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the channel exceeds 13 bits.
+    pub fn current_cluster_channel(mut self, channel: u16) -> Result<Self, ValueRangeError> {
+        if channel > 0x1fff {
+            return Err(ValueRangeError);
+        }
+        self.current_cluster_channel = Some(channel);
+        Ok(self)
+    }
+
+    /// Adds a channel to the list of additional network beacon channels.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the channel exceeds 13 bits, or if 3 channels have already been added.
+    pub fn additional_network_beacon_channel(
+        mut self,
+        channel: u16,
+    ) -> Result<Self, ValueRangeError> {
+        let slot = self
+            .additional_channels
+            .get_mut(usize::from(self.additional_channels_count))
+            .ok_or(ValueRangeError)?;
+        if channel > 0x1fff {
+            return Err(ValueRangeError);
+        }
+        *slot = channel;
+        self.additional_channels_count += 1;
+        Ok(self)
+    }
+}
+
+impl SerializeIe for NetworkBeaconBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::NETWORK_BEACON);
+
+    fn payload_len(&self) -> usize {
+        NETWORK_BEACON_FIXED_LEN
+            + usize::from(self.clusters_max_tx_power.is_some())
+            + 2 * usize::from(self.current_cluster_channel.is_some())
+            + 2 * usize::from(self.additional_channels_count)
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[
+            (u8::from(self.clusters_max_tx_power.is_some()) << 4)
+                | (u8::from(self.power_constraints) << 3)
+                | (u8::from(self.current_cluster_channel.is_some()) << 2)
+                | self.additional_channels_count,
+            (self.network_beacon_period << 4) | self.cluster_beacon_period,
+        ])?;
+        w.write_all(&self.next_cluster_channel.to_be_bytes())?;
+        w.write_all(&self.time_to_next.to_be_bytes())?;
+        if let Some(power) = self.clusters_max_tx_power {
+            w.write_all(&[power])?;
+        }
+        if let Some(channel) = self.current_cluster_channel {
+            w.write_all(&channel.to_be_bytes())?;
+        }
+        for channel in &self.additional_channels[..usize::from(self.additional_channels_count)] {
+            w.write_all(&channel.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// View on a Cluster Beacon message.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ClusterBeacon<'buf>(&'buf [u8]);
+
+impl<'buf> ClusterBeacon<'buf> {
+    /// Interprets the payload of a Cluster Beacon IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the one announced by its flags.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if cluster_beacon_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        Ok(Self(payload))
+    }
+
+    fn flags(&self) -> u8 {
+        self.0[1]
+    }
+
+    /// The 8-bit System Frame Number.
+    #[must_use]
+    pub fn system_frame_number(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// The Power Const flag: Whether the FT operates under power constraints.
+    #[must_use]
+    pub fn power_constraints(&self) -> bool {
+        flag(self.flags(), 3) != 0
+    }
+
+    /// The coded Network Beacon Period; see [`network_beacon_period_ms()`].
+    #[must_use]
+    pub fn network_beacon_period(&self) -> u8 {
+        self.0[2] >> 4
+    }
+
+    /// The coded Cluster Beacon Period; see [`cluster_beacon_period_ms()`].
+    #[must_use]
+    pub fn cluster_beacon_period(&self) -> u8 {
+        self.0[2] & 0x0f
+    }
+
+    /// The 4-bit Count To Trigger field, which is the number of measurements after which a
+    /// mobility-triggering condition is acted on (coded as that number minus one).
+    #[must_use]
+    pub fn count_to_trigger(&self) -> u8 {
+        self.0[3] >> 4
+    }
+
+    /// The coded Relative Quality; see [`quality_db()`].
+    #[must_use]
+    pub fn relative_quality(&self) -> u8 {
+        (self.0[3] >> 2) & 0x03
+    }
+
+    /// The coded Minimum Quality; see [`quality_db()`].
+    #[must_use]
+    pub fn minimum_quality(&self) -> u8 {
+        self.0[3] & 0x03
+    }
+
+    /// The coded Clusters Max TX Power, if present.
+    ///
+    /// See [`crate::pcc::transmit_power_dbm()`] for its meaning.
+    #[must_use]
+    pub fn clusters_max_tx_power(&self) -> Option<u8> {
+        (flag(self.flags(), 4) != 0).then(|| self.0[4] & 0x0f)
+    }
+
+    /// The Frame Offset, if present.
+    ///
+    /// This assumes that the frame offset is 8 bits long, which is the case for subcarrier
+    /// scaling factors µ up to 4.
+    #[must_use]
+    pub fn frame_offset(&self) -> Option<u8> {
+        let offset = 4 + flag(self.flags(), 4);
+        (flag(self.flags(), 2) != 0).then(|| self.0[offset])
+    }
+
+    /// The channel on which the next cluster beacon is sent, if it is not the current channel.
+    #[must_use]
+    pub fn next_cluster_channel(&self) -> Option<u16> {
+        let offset = 4 + flag(self.flags(), 4) + flag(self.flags(), 2);
+        (flag(self.flags(), 1) != 0).then(|| channel(&self.0[offset..]))
+    }
+
+    /// Time to the next cluster beacon, in µs, if present.
+    #[must_use]
+    pub fn time_to_next(&self) -> Option<u32> {
+        let offset = 4 + flag(self.flags(), 4) + flag(self.flags(), 2) + 2 * flag(self.flags(), 1);
+        (flag(self.flags(), 0) != 0).then(|| {
+            u32::from_be_bytes([
+                self.0[offset],
+                self.0[offset + 1],
+                self.0[offset + 2],
+                self.0[offset + 3],
+            ])
+        })
+    }
+}
+
+/// Builder for a Cluster Beacon message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ClusterBeaconBuilder {
+    system_frame_number: u8,
+    clusters_max_tx_power: Option<u8>,
+    power_constraints: bool,
+    frame_offset: Option<u8>,
+    next_cluster_channel: Option<u16>,
+    time_to_next: Option<u32>,
+    network_beacon_period: u8,
+    cluster_beacon_period: u8,
+    count_to_trigger: u8,
+    relative_quality: u8,
+    minimum_quality: u8,
+}
+
+impl ClusterBeaconBuilder {
+    /// Creates a Cluster Beacon with its mandatory fields; count to trigger and qualities start
+    /// out as 0.
+    ///
+    /// The periods are given as codes (see [`network_beacon_period_ms()`] and
+    /// [`cluster_beacon_period_ms()`]).
+    ///
+    /// # Errors
+    ///
+    /// This errs if a period code exceeds 4 bits.
+    pub fn new(
+        system_frame_number: u8,
+        network_beacon_period: u8,
+        cluster_beacon_period: u8,
+    ) -> Result<Self, ValueRangeError> {
+        if network_beacon_period > 0x0f || cluster_beacon_period > 0x0f {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            system_frame_number,
+            clusters_max_tx_power: None,
+            power_constraints: false,
+            frame_offset: None,
+            next_cluster_channel: None,
+            time_to_next: None,
+            network_beacon_period,
+            cluster_beacon_period,
+            count_to_trigger: 0,
+            relative_quality: 0,
+            minimum_quality: 0,
+        })
+    }
+
+    /// Sets the coded Clusters Max TX Power.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the code exceeds 4 bits.
+    pub fn clusters_max_tx_power(mut self, code: u8) -> Result<Self, ValueRangeError> {
+        if code > 0x0f {
+            return Err(ValueRangeError);
+        }
+        self.clusters_max_tx_power = Some(code);
+        Ok(self)
+    }
+
+    /// Sets the Power Const flag.
+    #[must_use]
+    pub fn power_constraints(mut self, power_constraints: bool) -> Self {
+        self.power_constraints = power_constraints;
+        self
+    }
+
+    /// Sets the (8-bit) Frame Offset.
+    #[must_use]
+    pub fn frame_offset(mut self, frame_offset: u8) -> Self {
+        self.frame_offset = Some(frame_offset);
+        self
+    }
+
+    /// Sets the channel of the next cluster beacon.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the channel exceeds 13 bits.
+    pub fn next_cluster_channel(mut self, channel: u16) -> Result<Self, ValueRangeError> {
+        if channel > 0x1fff {
+            return Err(ValueRangeError);
+        }
+        self.next_cluster_channel = Some(channel);
+        Ok(self)
+    }
+
+    /// Sets the time to the next cluster beacon in µs.
+    #[must_use]
+    pub fn time_to_next(mut self, time_to_next: u32) -> Self {
+        self.time_to_next = Some(time_to_next);
+        self
+    }
+
+    /// Sets the mobility parameters in their coded forms.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the count to trigger exceeds 4 bits, or a quality exceeds 2 bits.
+    pub fn mobility(
+        mut self,
+        count_to_trigger: u8,
+        relative_quality: u8,
+        minimum_quality: u8,
+    ) -> Result<Self, ValueRangeError> {
+        if count_to_trigger > 0x0f || relative_quality > 0x03 || minimum_quality > 0x03 {
+            return Err(ValueRangeError);
+        }
+        self.count_to_trigger = count_to_trigger;
+        self.relative_quality = relative_quality;
+        self.minimum_quality = minimum_quality;
+        Ok(self)
+    }
+}
+
+impl SerializeIe for ClusterBeaconBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::CLUSTER_BEACON);
+
+    fn payload_len(&self) -> usize {
+        4 + usize::from(self.clusters_max_tx_power.is_some())
+            + usize::from(self.frame_offset.is_some())
+            + 2 * usize::from(self.next_cluster_channel.is_some())
+            + 4 * usize::from(self.time_to_next.is_some())
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[
+            self.system_frame_number,
+            (u8::from(self.clusters_max_tx_power.is_some()) << 4)
+                | (u8::from(self.power_constraints) << 3)
+                | (u8::from(self.frame_offset.is_some()) << 2)
+                | (u8::from(self.next_cluster_channel.is_some()) << 1)
+                | u8::from(self.time_to_next.is_some()),
+            (self.network_beacon_period << 4) | self.cluster_beacon_period,
+            (self.count_to_trigger << 4) | (self.relative_quality << 2) | self.minimum_quality,
+        ])?;
+        if let Some(power) = self.clusters_max_tx_power {
+            w.write_all(&[power])?;
+        }
+        if let Some(frame_offset) = self.frame_offset {
+            w.write_all(&[frame_offset])?;
+        }
+        if let Some(channel) = self.next_cluster_channel {
+            w.write_all(&channel.to_be_bytes())?;
+        }
+        if let Some(time_to_next) = self.time_to_next {
+            w.write_all(&time_to_next.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cluster_beacon() {
+        // From the beacon used across tests, as sent by the dect_shell
+        let payload = [176, 16, 6, 0, 13];
+        let beacon = ClusterBeacon::parse(&payload).unwrap();
+        assert_eq!(beacon.system_frame_number(), 176);
+        assert_eq!(beacon.clusters_max_tx_power(), Some(13));
+        assert_eq!(crate::pcc::transmit_power_dbm(13), 19);
+        assert!(!beacon.power_constraints());
+        assert_eq!(
+            network_beacon_period_ms(beacon.network_beacon_period()),
+            Some(50)
+        );
+        assert_eq!(
+            cluster_beacon_period_ms(beacon.cluster_beacon_period()),
+            Some(2000)
+        );
+        assert_eq!(beacon.count_to_trigger(), 0);
+        assert_eq!(quality_db(beacon.relative_quality()), 0);
+        assert_eq!(quality_db(beacon.minimum_quality()), 0);
+        assert_eq!(beacon.frame_offset(), None);
+        assert_eq!(beacon.next_cluster_channel(), None);
+        assert_eq!(beacon.time_to_next(), None);
+
+        let builder = ClusterBeaconBuilder::new(176, 0, 6)
+            .unwrap()
+            .clusters_max_tx_power(13)
+            .unwrap();
+        let mut written = [0; 7];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(builder.ie_len(), 7);
+        assert_eq!(written, [73, 5, 176, 16, 6, 0, 13]);
+
+        let builder = ClusterBeaconBuilder::new(1, 2, 3)
+            .unwrap()
+            .power_constraints(true)
+            .frame_offset(7)
+            .next_cluster_channel(1667)
+            .unwrap()
+            .time_to_next(100_000)
+            .mobility(5, 1, 2)
+            .unwrap();
+        let mut written = [0; 11];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        assert_eq!(builder.payload_len(), 11);
+        let beacon = ClusterBeacon::parse(&written).unwrap();
+        assert!(beacon.power_constraints());
+        assert_eq!(beacon.clusters_max_tx_power(), None);
+        assert_eq!(beacon.frame_offset(), Some(7));
+        assert_eq!(beacon.next_cluster_channel(), Some(1667));
+        assert_eq!(beacon.time_to_next(), Some(100_000));
+        assert_eq!(beacon.count_to_trigger(), 5);
+        assert_eq!(quality_db(beacon.relative_quality()), 3);
+        assert_eq!(quality_db(beacon.minimum_quality()), 6);
+
+        ClusterBeacon::parse(&written[..10]).unwrap_err();
+    }
+
+    #[test]
+    fn test_network_beacon() {
+        let payload = [
+            0x15, 0x62, 0x06, 0x81, 0x00, 0x0f, 0x42, 0x40, 0x0d, 0x06, 0x81, 0x06, 0x83,
+        ];
+        let beacon = NetworkBeacon::parse(&payload).unwrap();
+        assert_eq!(beacon.clusters_max_tx_power(), Some(13));
+        assert!(!beacon.power_constraints());
+        assert_eq!(
+            network_beacon_period_ms(beacon.network_beacon_period()),
+            Some(4000)
+        );
+        assert_eq!(
+            cluster_beacon_period_ms(beacon.cluster_beacon_period()),
+            Some(100)
+        );
+        assert_eq!(beacon.next_cluster_channel(), 1665);
+        assert_eq!(beacon.time_to_next(), 1_000_000);
+        assert_eq!(beacon.current_cluster_channel(), Some(1665));
+        assert!(beacon.additional_network_beacon_channels().eq([1667]));
+
+        let builder = NetworkBeaconBuilder::new(6, 2, 1665, 1_000_000)
+            .unwrap()
+            .clusters_max_tx_power(13)
+            .unwrap()
+            .current_cluster_channel(1665)
+            .unwrap()
+            .additional_network_beacon_channel(1667)
+            .unwrap();
+        let mut written = [0; 15];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(written[..2], [0x48, 13]);
+        assert_eq!(written[2..], payload);
+
+        // Only 3 additional channels fit
+        let builder = NetworkBeaconBuilder::new(0, 0, 1, 0)
+            .unwrap()
+            .power_constraints(true)
+            .additional_network_beacon_channel(3)
+            .unwrap()
+            .additional_network_beacon_channel(5)
+            .unwrap()
+            .additional_network_beacon_channel(7)
+            .unwrap();
+        let mut written = [0; 14];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        let beacon = NetworkBeacon::parse(&written).unwrap();
+        assert!(beacon.power_constraints());
+        assert_eq!(beacon.current_cluster_channel(), None);
+        assert!(beacon.additional_network_beacon_channels().eq([3, 5, 7]));
+        builder.additional_network_beacon_channel(9).unwrap_err();
+
+        NetworkBeacon::parse(&payload[..12]).unwrap_err();
+    }
+}
@@ -0,0 +1,622 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! RD Capability and RD Capability Short messages
+//!
+//! These are the payloads of the [`RD_CAPABILITY`][numbers::mac_ie::ie6bit::RD_CAPABILITY] and
+//! [`RD_CAPABILITY_SHORT`][numbers::mac_ie::ie5bit_len1::RD_CAPABILITY_SHORT] IEs as defined in
+//! Section 6.4.3.5 of ETSI TS 103 636-4 V2.1.1.
+//!
+//! Like in the [beacons][super::beacon], PHY related fields are exposed in their coded form, and
+//! free functions in this module convert them into their values.
+
+use super::{AnyIeType, SerializeIe, flag};
+use crate::{ParsingError, ValueRangeError};
+use ts_103_636_numbers as numbers;
+
+coded_enum! {
+    /// Operating modes a radio device supports.
+    pub enum OperatingModes {
+        /// PT mode only.
+        PtOnly = 0,
+        /// FT mode only.
+        FtOnly = 1,
+        /// Both PT and FT mode.
+        PtAndFt = 2,
+    }
+}
+
+/// Converts a coded Max NSS for RX or RX for TX Diversity into a number of spatial streams or
+/// antennas, respectively.
+#[must_use]
+pub fn spatial_streams(code: u8) -> u8 {
+    1 << (code & 0x03)
+}
+
+/// Converts a coded RX Gain into dB.
+///
+/// Returns `None` for reserved values.
+#[must_use]
+pub fn rx_gain_db(code: u8) -> Option<i8> {
+    #[expect(
+        clippy::cast_possible_wrap,
+        reason = "code is limited to values up to 8"
+    )]
+    (code <= 8).then(|| -10 + 2 * code as i8)
+}
+
+/// Converts a coded Max MCS into the highest supported MCS index.
+///
+/// Returns `None` for reserved values.
+#[must_use]
+pub fn max_mcs(code: u8) -> Option<u8> {
+    (code <= 9).then(|| code + 2)
+}
+
+/// Converts a coded Soft-buffer Size into bytes.
+///
+/// Returns `None` for reserved values.
+#[must_use]
+pub fn soft_buffer_size_bytes(code: u8) -> Option<u32> {
+    const TABLE: [u32; 9] = [
+        16_000, 25_344, 32_000, 64_000, 128_000, 256_000, 512_000, 1_024_000, 2_048_000,
+    ];
+    TABLE.get(usize::from(code)).copied()
+}
+
+/// Converts a coded Number of HARQ Processes into the number of processes.
+#[must_use]
+pub fn harq_processes(code: u8) -> u8 {
+    1 << (code & 0x03)
+}
+
+/// Converts a coded subcarrier scaling factor µ into its value.
+///
+/// Returns `None` for reserved values.
+#[must_use]
+pub fn subcarrier_scaling_factor(code: u8) -> Option<u8> {
+    (code <= 3).then(|| 1 << code)
+}
+
+/// Converts a coded Fourier transform scaling factor β into its value.
+///
+/// Returns `None` for reserved values.
+#[must_use]
+pub fn fourier_transform_scaling_factor(code: u8) -> Option<u8> {
+    const TABLE: [u8; 6] = [1, 2, 4, 8, 12, 16];
+    TABLE.get(usize::from(code)).copied()
+}
+
+/// Maximum number of additional PHY capabilities.
+const MAX_ADDITIONAL: usize = 7;
+
+/// Length of the RD Capability fields that are always present.
+const RD_CAPABILITY_FIXED_LEN: usize = 7;
+
+/// Length of an additional PHY capability.
+const ADDITIONAL_LEN: usize = 5;
+
+/// The PHY capabilities of a radio device for one combination of µ and β, in coded form.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PhyCapability {
+    /// The 3-bit RD Power Class.
+    pub rd_power_class: u8,
+    /// The 2-bit Max NSS for RX; see [`spatial_streams()`].
+    pub max_nss_rx: u8,
+    /// The 2-bit RX for TX Diversity; see [`spatial_streams()`].
+    pub rx_for_tx_diversity: u8,
+    /// The 4-bit RX Gain; see [`rx_gain_db()`].
+    pub rx_gain: u8,
+    /// The 4-bit Max MCS; see [`max_mcs()`].
+    pub max_mcs: u8,
+    /// The 4-bit Soft-buffer Size; see [`soft_buffer_size_bytes()`].
+    pub soft_buffer_size: u8,
+    /// The 2-bit Number of HARQ Processes; see [`harq_processes()`].
+    pub harq_processes: u8,
+    /// The 4-bit HARQ Feedback Delay, in subslots.
+    pub harq_feedback_delay: u8,
+}
+
+impl PhyCapability {
+    /// Reads the 4 bytes that are shared between the main capabilities and additional ones; the
+    /// lower 4 bits of the last byte are ignored.
+    fn parse(data: &[u8]) -> Self {
+        Self {
+            rd_power_class: (data[0] >> 4) & 0x07,
+            max_nss_rx: (data[0] >> 2) & 0x03,
+            rx_for_tx_diversity: data[0] & 0x03,
+            rx_gain: data[1] >> 4,
+            max_mcs: data[1] & 0x0f,
+            soft_buffer_size: data[2] >> 4,
+            harq_processes: (data[2] >> 2) & 0x03,
+            harq_feedback_delay: data[3] >> 4,
+        }
+    }
+
+    fn check(self) -> Result<(), ValueRangeError> {
+        if self.rd_power_class > 0x07
+            || self.max_nss_rx > 0x03
+            || self.rx_for_tx_diversity > 0x03
+            || self.rx_gain > 0x0f
+            || self.max_mcs > 0x0f
+            || self.soft_buffer_size > 0x0f
+            || self.harq_processes > 0x03
+            || self.harq_feedback_delay > 0x0f
+        {
+            return Err(ValueRangeError);
+        }
+        Ok(())
+    }
+
+    /// Produces the 4 shared bytes, with the lower 4 bits of the last byte left 0.
+    fn to_bytes(self) -> [u8; 4] {
+        [
+            (self.rd_power_class << 4) | (self.max_nss_rx << 2) | self.rx_for_tx_diversity,
+            (self.rx_gain << 4) | self.max_mcs,
+            (self.soft_buffer_size << 4) | (self.harq_processes << 2),
+            self.harq_feedback_delay << 4,
+        ]
+    }
+}
+
+/// PHY capabilities that apply to a particular combination of µ and β.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AdditionalPhyCapability {
+    /// The coded 3-bit µ; see [`subcarrier_scaling_factor()`].
+    pub mu: u8,
+    /// The coded 4-bit β; see [`fourier_transform_scaling_factor()`].
+    pub beta: u8,
+    /// The capabilities at that µ and β.
+    pub capability: PhyCapability,
+}
+
+fn set_flag(byte: u8, bit: u8, value: bool) -> u8 {
+    (byte & !(1 << bit)) | (u8::from(value) << bit)
+}
+
+/// Length of an RD Capability payload as indicated by its leading byte.
+///
+/// Returns `None` if the data is too short to tell.
+fn rd_capability_length(data: &[u8]) -> Option<usize> {
+    Some(RD_CAPABILITY_FIXED_LEN + ADDITIONAL_LEN * usize::from(data.first()? >> 5))
+}
+
+/// View on an RD Capability message.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RdCapability<'buf>(&'buf [u8]);
+
+impl<'buf> RdCapability<'buf> {
+    /// Interprets the payload of an RD Capability IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the number of PHY capabilities, or if the
+    /// operating modes have the reserved value.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if rd_capability_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        OperatingModes::from_code((payload[1] >> 2) & 0x03).ok_or(ParsingError)?;
+        Ok(Self(payload))
+    }
+
+    /// The 5-bit Release field, indicating the release of the specification the device
+    /// implements.
+    #[must_use]
+    pub fn release(&self) -> u8 {
+        self.0[0] & 0x1f
+    }
+
+    /// Whether the device supports group assignment.
+    #[must_use]
+    pub fn group_assignment(&self) -> bool {
+        flag(self.0[1], 5) != 0
+    }
+
+    /// Whether the device supports paging.
+    #[must_use]
+    pub fn paging(&self) -> bool {
+        flag(self.0[1], 4) != 0
+    }
+
+    /// The operating modes the device supports.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "Reserved values were excluded at parsing"
+    )]
+    #[must_use]
+    pub fn operating_modes(&self) -> OperatingModes {
+        OperatingModes::from_code((self.0[1] >> 2) & 0x03).expect("Checked at parsing")
+    }
+
+    /// Whether the device supports mesh operation.
+    #[must_use]
+    pub fn mesh(&self) -> bool {
+        flag(self.0[1], 1) != 0
+    }
+
+    /// Whether the device supports scheduled data transfer.
+    #[must_use]
+    pub fn scheduled_access(&self) -> bool {
+        flag(self.0[1], 0) != 0
+    }
+
+    /// The 3-bit MAC Security field; 0 indicates no support, 1 support for mode 1.
+    #[must_use]
+    pub fn mac_security(&self) -> u8 {
+        self.0[2] >> 5
+    }
+
+    /// The 3-bit DLC Service Type field, indicating which DLC service types the device supports.
+    #[must_use]
+    pub fn dlc_service_type(&self) -> u8 {
+        (self.0[2] >> 2) & 0x07
+    }
+
+    /// The PHY capabilities at µ = 1 and β = 1.
+    #[must_use]
+    pub fn phy_capability(&self) -> PhyCapability {
+        PhyCapability::parse(&self.0[3..7])
+    }
+
+    /// Whether the device supports the delayed response in random access (D-delay).
+    #[must_use]
+    pub fn d_delay(&self) -> bool {
+        flag(self.0[6], 3) != 0
+    }
+
+    /// Whether the device operates half duplex.
+    #[must_use]
+    pub fn half_duplex(&self) -> bool {
+        flag(self.0[6], 2) != 0
+    }
+
+    /// The PHY capabilities for other combinations of µ and β.
+    pub fn additional_phy_capabilities(
+        &self,
+    ) -> impl Iterator<Item = AdditionalPhyCapability> + use<'buf> {
+        self.0[RD_CAPABILITY_FIXED_LEN..]
+            .chunks_exact(ADDITIONAL_LEN)
+            .map(|chunk| AdditionalPhyCapability {
+                mu: (chunk[0] >> 4) & 0x07,
+                beta: chunk[0] & 0x0f,
+                capability: PhyCapability::parse(&chunk[1..]),
+            })
+    }
+}
+
+/// Builder for an RD Capability message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RdCapabilityBuilder {
+    release: u8,
+    /// The feature flags in their position in the second byte
+    features: u8,
+    operating_modes: OperatingModes,
+    mac_security: u8,
+    dlc_service_type: u8,
+    phy_capability: PhyCapability,
+    /// The D-delay and half duplex flags in their position in the last PHY capability byte
+    phy_flags: u8,
+    additional: [Option<AdditionalPhyCapability>; MAX_ADDITIONAL],
+}
+
+impl RdCapabilityBuilder {
+    /// Creates an RD Capability message with the PHY capabilities at µ = 1 and β = 1.
+    ///
+    /// All optional features start out unsupported.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the release exceeds 5 bits, or any PHY capability exceeds its field.
+    pub fn new(
+        release: u8,
+        operating_modes: OperatingModes,
+        phy_capability: PhyCapability,
+    ) -> Result<Self, ValueRangeError> {
+        if release > 0x1f {
+            return Err(ValueRangeError);
+        }
+        phy_capability.check()?;
+        Ok(Self {
+            release,
+            features: 0,
+            operating_modes,
+            mac_security: 0,
+            dlc_service_type: 0,
+            phy_capability,
+            phy_flags: 0,
+            additional: [None; MAX_ADDITIONAL],
+        })
+    }
+
+    /// Sets whether group assignment is supported.
+    #[must_use]
+    pub fn group_assignment(mut self, group_assignment: bool) -> Self {
+        self.features = set_flag(self.features, 5, group_assignment);
+        self
+    }
+
+    /// Sets whether paging is supported.
+    #[must_use]
+    pub fn paging(mut self, paging: bool) -> Self {
+        self.features = set_flag(self.features, 4, paging);
+        self
+    }
+
+    /// Sets whether mesh operation is supported.
+    #[must_use]
+    pub fn mesh(mut self, mesh: bool) -> Self {
+        self.features = set_flag(self.features, 1, mesh);
+        self
+    }
+
+    /// Sets whether scheduled data transfer is supported.
+    #[must_use]
+    pub fn scheduled_access(mut self, scheduled_access: bool) -> Self {
+        self.features = set_flag(self.features, 0, scheduled_access);
+        self
+    }
+
+    /// Sets the MAC Security field.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the value exceeds 3 bits.
+    pub fn mac_security(mut self, mac_security: u8) -> Result<Self, ValueRangeError> {
+        if mac_security > 0x07 {
+            return Err(ValueRangeError);
+        }
+        self.mac_security = mac_security;
+        Ok(self)
+    }
+
+    /// Sets the DLC Service Type field.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the value exceeds 3 bits.
+    pub fn dlc_service_type(mut self, dlc_service_type: u8) -> Result<Self, ValueRangeError> {
+        if dlc_service_type > 0x07 {
+            return Err(ValueRangeError);
+        }
+        self.dlc_service_type = dlc_service_type;
+        Ok(self)
+    }
+
+    /// Sets whether the delayed response in random access (D-delay) is supported.
+    #[must_use]
+    pub fn d_delay(mut self, d_delay: bool) -> Self {
+        self.phy_flags = set_flag(self.phy_flags, 3, d_delay);
+        self
+    }
+
+    /// Sets whether the device operates half duplex.
+    #[must_use]
+    pub fn half_duplex(mut self, half_duplex: bool) -> Self {
+        self.phy_flags = set_flag(self.phy_flags, 2, half_duplex);
+        self
+    }
+
+    /// Adds PHY capabilities for another combination of µ and β.
+    ///
+    /// # Errors
+    ///
+    /// This errs if any value exceeds its field, or if 7 additional capabilities have already
+    /// been added.
+    pub fn additional_phy_capability(
+        mut self,
+        additional: AdditionalPhyCapability,
+    ) -> Result<Self, ValueRangeError> {
+        if additional.mu > 0x07 || additional.beta > 0x0f {
+            return Err(ValueRangeError);
+        }
+        additional.capability.check()?;
+        let slot = self
+            .additional
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(ValueRangeError)?;
+        *slot = Some(additional);
+        Ok(self)
+    }
+
+    fn additional(&self) -> impl Iterator<Item = &AdditionalPhyCapability> {
+        self.additional.iter().map_while(Option::as_ref)
+    }
+}
+
+impl SerializeIe for RdCapabilityBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::RD_CAPABILITY);
+
+    fn payload_len(&self) -> usize {
+        RD_CAPABILITY_FIXED_LEN + ADDITIONAL_LEN * self.additional().count()
+    }
+
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "there are at most 7 additional capabilities"
+    )]
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        let mut phy = self.phy_capability.to_bytes();
+        phy[3] |= self.phy_flags;
+        w.write_all(&[
+            ((self.additional().count() as u8) << 5) | self.release,
+            self.features | (self.operating_modes.code() << 2),
+            (self.mac_security << 5) | (self.dlc_service_type << 2),
+        ])?;
+        w.write_all(&phy)?;
+        for additional in self.additional() {
+            w.write_all(&[(additional.mu << 4) | additional.beta])?;
+            w.write_all(&additional.capability.to_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// View on an RD Capability Short message.
+///
+/// Its single byte carries the 3-bit Release, the Operating Modes and the 3-bit RD Power Class.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RdCapabilityShort<'buf>(&'buf [u8; 1]);
+
+impl<'buf> RdCapabilityShort<'buf> {
+    /// Interprets the payload of an RD Capability Short IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload is not 1 byte long, or if the operating modes have the reserved
+    /// value.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        let payload: &[u8; 1] = payload.try_into().map_err(|_| ParsingError)?;
+        OperatingModes::from_code((payload[0] >> 3) & 0x03).ok_or(ParsingError)?;
+        Ok(Self(payload))
+    }
+
+    /// The 3-bit Release field.
+    #[must_use]
+    pub fn release(&self) -> u8 {
+        self.0[0] >> 5
+    }
+
+    /// The operating modes the device supports.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "Reserved values were excluded at parsing"
+    )]
+    #[must_use]
+    pub fn operating_modes(&self) -> OperatingModes {
+        OperatingModes::from_code((self.0[0] >> 3) & 0x03).expect("Checked at parsing")
+    }
+
+    /// The 3-bit RD Power Class.
+    #[must_use]
+    pub fn rd_power_class(&self) -> u8 {
+        self.0[0] & 0x07
+    }
+}
+
+/// Builder for an RD Capability Short message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RdCapabilityShortBuilder {
+    byte: u8,
+}
+
+impl RdCapabilityShortBuilder {
+    /// Creates an RD Capability Short message.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the release or power class exceed 3 bits.
+    pub fn new(
+        release: u8,
+        operating_modes: OperatingModes,
+        rd_power_class: u8,
+    ) -> Result<Self, ValueRangeError> {
+        if release > 0x07 || rd_power_class > 0x07 {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            byte: (release << 5) | (operating_modes.code() << 3) | rd_power_class,
+        })
+    }
+}
+
+impl SerializeIe for RdCapabilityShortBuilder {
+    const IE_TYPE: AnyIeType =
+        AnyIeType::Type5bit(numbers::mac_ie::ie5bit_len1::RD_CAPABILITY_SHORT);
+
+    fn payload_len(&self) -> usize {
+        1
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[self.byte])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rd_capability() {
+        let phy = PhyCapability {
+            rd_power_class: 3,
+            max_nss_rx: 0,
+            rx_for_tx_diversity: 0,
+            rx_gain: 5,
+            max_mcs: 2,
+            soft_buffer_size: 1,
+            harq_processes: 2,
+            harq_feedback_delay: 4,
+        };
+        let builder = RdCapabilityBuilder::new(1, OperatingModes::PtAndFt, phy)
+            .unwrap()
+            .scheduled_access(true)
+            .d_delay(true);
+        let mut written = [0; 9];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(
+            written,
+            [0x40 | 0x14, 7, 0x01, 0x09, 0x00, 0x30, 0x52, 0x18, 0x48]
+        );
+
+        let capability = RdCapability::parse(&written[2..]).unwrap();
+        assert_eq!(capability.release(), 1);
+        assert_eq!(capability.operating_modes(), OperatingModes::PtAndFt);
+        assert!(capability.scheduled_access());
+        assert!(!capability.mesh());
+        assert!(!capability.paging());
+        assert_eq!(capability.mac_security(), 0);
+        assert_eq!(capability.phy_capability(), phy);
+        assert!(capability.d_delay());
+        assert!(!capability.half_duplex());
+        assert_eq!(capability.additional_phy_capabilities().count(), 0);
+        assert_eq!(rx_gain_db(phy.rx_gain), Some(0));
+        assert_eq!(max_mcs(phy.max_mcs), Some(4));
+        assert_eq!(soft_buffer_size_bytes(phy.soft_buffer_size), Some(25_344));
+        assert_eq!(harq_processes(phy.harq_processes), 4);
+
+        let additional = AdditionalPhyCapability {
+            mu: 1,
+            beta: 4,
+            capability: PhyCapability { max_mcs: 9, ..phy },
+        };
+        let builder = builder
+            .mac_security(1)
+            .unwrap()
+            .additional_phy_capability(additional)
+            .unwrap();
+        let mut written = [0; 12];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        assert_eq!(builder.payload_len(), 12);
+        let capability = RdCapability::parse(&written).unwrap();
+        assert_eq!(capability.mac_security(), 1);
+        assert_eq!(capability.phy_capability(), phy);
+        assert!(capability.additional_phy_capabilities().eq([additional]));
+        assert_eq!(subcarrier_scaling_factor(additional.mu), Some(2));
+        assert_eq!(fourier_transform_scaling_factor(additional.beta), Some(12));
+
+        RdCapability::parse(&written[..11]).unwrap_err();
+    }
+
+    #[test]
+    fn test_rd_capability_short() {
+        let mut written = [0; 2];
+        RdCapabilityShortBuilder::new(1, OperatingModes::FtOnly, 3)
+            .unwrap()
+            .serialize(&mut &mut written[..])
+            .unwrap();
+        assert_eq!(written, [0xe2, 0x2b]);
+        let capability = RdCapabilityShort::parse(&written[1..]).unwrap();
+        assert_eq!(capability.release(), 1);
+        assert_eq!(capability.operating_modes(), OperatingModes::FtOnly);
+        assert_eq!(capability.rd_power_class(), 3);
+        RdCapabilityShort::parse(&[0x18]).unwrap_err();
+    }
+}
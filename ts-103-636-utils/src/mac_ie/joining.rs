@@ -0,0 +1,281 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Joining Beacon and Joining Information messages
+//!
+//! These are the payloads of the [`JOINING_BEACON`][numbers::mac_ie::ie6bit::JOINING_BEACON] and
+//! [`JOINING_INFORMATION`][numbers::mac_ie::ie6bit::JOINING_INFORMATION] IEs as defined in Sections
+//! 6.4.3.18 and 6.4.3.19 of ETSI TS 103 636-4 V2.1.1, which are used in the onboarding procedure
+//! of ETSI TS 103 636-1 Figure 7.2-2: A network announces in its beacons that it accepts joining
+//! devices, and the joining device responds with its own information.
+//!
+//! Both messages share their structure: a version, an optional joining public key and a list of
+//! endpoints (EPs) that are used during onboarding.
+
+use super::{AnyIeType, SerializeIe};
+use crate::{ParsingError, ValueRangeError};
+use numbers::endpoint_multiplexing::EndpointMultiplexingAddress;
+use ts_103_636_numbers as numbers;
+
+/// The only version of the joining messages that is understood.
+const VERSION: u8 = 0;
+
+/// Maximum number of EPs in a joining message.
+const MAX_ENDPOINTS: usize = 15;
+
+/// Length of a joining payload as indicated by its leading fields.
+///
+/// Returns `None` if the data is too short to tell.
+fn joining_length(data: &[u8]) -> Option<usize> {
+    let flags = *data.first()?;
+    let endpoints = usize::from(flags & 0x0f);
+    let key = if (flags >> 4) & 1 != 0 {
+        1 + usize::from(*data.get(1)?)
+    } else {
+        0
+    };
+    Some(1 + key + 2 * endpoints)
+}
+
+/// Validates a joining payload; shared by both views.
+fn check(payload: &[u8]) -> Result<(), ParsingError> {
+    if joining_length(payload) != Some(payload.len()) || payload[0] >> 5 != VERSION {
+        return Err(ParsingError);
+    }
+    Ok(())
+}
+
+fn public_key(payload: &[u8]) -> Option<&[u8]> {
+    ((payload[0] >> 4) & 1 != 0).then(|| &payload[2..2 + usize::from(payload[1])])
+}
+
+fn endpoints(payload: &[u8]) -> impl Iterator<Item = EndpointMultiplexingAddress> + use<'_> {
+    let start = payload.len() - 2 * usize::from(payload[0] & 0x0f);
+    payload[start..]
+        .chunks_exact(2)
+        .map(|ep| u16::from_be_bytes([ep[0], ep[1]]).into())
+}
+
+/// View on a Joining Beacon message.
+///
+/// An FT announces through this that devices can join its network.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct JoiningBeacon<'buf>(&'buf [u8]);
+
+impl<'buf> JoiningBeacon<'buf> {
+    /// Interprets the payload of a Joining Beacon IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the version is not the one understood, or if the payload length does not
+    /// match the one announced in its leading fields.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        check(payload)?;
+        Ok(Self(payload))
+    }
+
+    /// The network's joining public key, if given.
+    #[must_use]
+    pub fn public_key(&self) -> Option<&'buf [u8]> {
+        public_key(self.0)
+    }
+
+    /// The EPs a joining device can address during onboarding.
+    pub fn endpoints(&self) -> impl Iterator<Item = EndpointMultiplexingAddress> + use<'buf> {
+        endpoints(self.0)
+    }
+}
+
+/// View on a Joining Information message.
+///
+/// A joining device responds through this to a [`JoiningBeacon`].
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct JoiningInformation<'buf>(&'buf [u8]);
+
+impl<'buf> JoiningInformation<'buf> {
+    /// Interprets the payload of a Joining Information IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the version is not the one understood, or if the payload length does not
+    /// match the one announced in its leading fields.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        check(payload)?;
+        Ok(Self(payload))
+    }
+
+    /// The joining device's public key, if given.
+    #[must_use]
+    pub fn public_key(&self) -> Option<&'buf [u8]> {
+        public_key(self.0)
+    }
+
+    /// The EPs the joining device supports during onboarding.
+    pub fn endpoints(&self) -> impl Iterator<Item = EndpointMultiplexingAddress> + use<'buf> {
+        endpoints(self.0)
+    }
+}
+
+/// Fields shared by the builders.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Fields<'a> {
+    public_key: Option<&'a [u8]>,
+    endpoints: &'a [EndpointMultiplexingAddress],
+}
+
+impl<'a> Fields<'a> {
+    fn new(endpoints: &'a [EndpointMultiplexingAddress]) -> Result<Self, ValueRangeError> {
+        if endpoints.len() > MAX_ENDPOINTS {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            public_key: None,
+            endpoints,
+        })
+    }
+
+    fn public_key(&mut self, public_key: &'a [u8]) -> Result<(), ValueRangeError> {
+        if public_key.len() > u8::MAX.into() {
+            return Err(ValueRangeError);
+        }
+        self.public_key = Some(public_key);
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        1 + self.public_key.map_or(0, |key| 1 + key.len()) + 2 * self.endpoints.len()
+    }
+
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "Lengths were checked when setting the fields"
+    )]
+    fn serialize<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[(VERSION << 5)
+            | (u8::from(self.public_key.is_some()) << 4)
+            | self.endpoints.len() as u8])?;
+        if let Some(key) = self.public_key {
+            w.write_all(&[key.len() as u8])?;
+            w.write_all(key)?;
+        }
+        for endpoint in self.endpoints {
+            w.write_all(&endpoint.0.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Builder for a Joining Beacon message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct JoiningBeaconBuilder<'a>(Fields<'a>);
+
+impl<'a> JoiningBeaconBuilder<'a> {
+    /// Creates a Joining Beacon offering the given EPs.
+    ///
+    /// # Errors
+    ///
+    /// This errs if more than 15 EPs are given.
+    pub fn new(endpoints: &'a [EndpointMultiplexingAddress]) -> Result<Self, ValueRangeError> {
+        Ok(Self(Fields::new(endpoints)?))
+    }
+
+    /// Includes the network's joining public key.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the key is longer than 255 bytes.
+    pub fn public_key(mut self, public_key: &'a [u8]) -> Result<Self, ValueRangeError> {
+        self.0.public_key(public_key)?;
+        Ok(self)
+    }
+}
+
+impl SerializeIe for JoiningBeaconBuilder<'_> {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::JOINING_BEACON);
+
+    fn payload_len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        self.0.serialize(w)
+    }
+}
+
+/// Builder for a Joining Information message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct JoiningInformationBuilder<'a>(Fields<'a>);
+
+impl<'a> JoiningInformationBuilder<'a> {
+    /// Creates a Joining Information message listing the given EPs.
+    ///
+    /// # Errors
+    ///
+    /// This errs if more than 15 EPs are given.
+    pub fn new(endpoints: &'a [EndpointMultiplexingAddress]) -> Result<Self, ValueRangeError> {
+        Ok(Self(Fields::new(endpoints)?))
+    }
+
+    /// Includes the joining device's public key.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the key is longer than 255 bytes.
+    pub fn public_key(mut self, public_key: &'a [u8]) -> Result<Self, ValueRangeError> {
+        self.0.public_key(public_key)?;
+        Ok(self)
+    }
+}
+
+impl SerializeIe for JoiningInformationBuilder<'_> {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::JOINING_INFORMATION);
+
+    fn payload_len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        self.0.serialize(w)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use numbers::endpoint_multiplexing::{CONFIGURATION_DATA_REQUEST, CONFIGURATION_DATA_RESPONSE};
+
+    #[test]
+    fn test_roundtrip() {
+        let endpoints = [CONFIGURATION_DATA_REQUEST, CONFIGURATION_DATA_RESPONSE];
+        let builder = JoiningBeaconBuilder::new(&endpoints)
+            .unwrap()
+            .public_key(&[1, 2, 3])
+            .unwrap();
+        let mut written = [0; 11];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(written, [0x5b, 9, 0x12, 3, 1, 2, 3, 0x80, 0x04, 0x80, 0x05]);
+        let beacon = JoiningBeacon::parse(&written[2..]).unwrap();
+        assert_eq!(beacon.public_key(), Some(&[1, 2, 3][..]));
+        assert!(beacon.endpoints().eq(endpoints));
+
+        let mut written = [0; 5];
+        JoiningInformationBuilder::new(&endpoints[..1])
+            .unwrap()
+            .serialize(&mut &mut written[..])
+            .unwrap();
+        assert_eq!(written, [0x5c, 3, 0x01, 0x80, 0x04]);
+        let information = JoiningInformation::parse(&written[2..]).unwrap();
+        assert_eq!(information.public_key(), None);
+        assert!(information.endpoints().eq([CONFIGURATION_DATA_REQUEST]));
+
+        // Unknown version
+        JoiningInformation::parse(&[0x21, 0x80, 0x04]).unwrap_err();
+        // Truncated key
+        JoiningBeacon::parse(&[0x10, 3, 1, 2]).unwrap_err();
+        JoiningBeaconBuilder::new(&[CONFIGURATION_DATA_REQUEST; 16]).unwrap_err();
+    }
+}
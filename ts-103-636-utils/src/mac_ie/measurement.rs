@@ -0,0 +1,800 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Neighbouring, Load Info and Measurement Report messages
+//!
+//! These are the payloads of the [`NEIGHBOURING`][numbers::mac_ie::ie6bit::NEIGHBOURING],
+//! [`LOAD_INFO`][numbers::mac_ie::ie6bit::LOAD_INFO] and
+//! [`MEASUREMENT_REPORT`][numbers::mac_ie::ie6bit::MEASUREMENT_REPORT] IEs as defined in Sections
+//! 6.4.3.6, 6.4.3.11 and 6.4.3.12 of ETSI TS 103 636-4 V2.1.1.
+//!
+//! RSSI and SNR values are exposed in dBm and dB, respectively, at the 1 dB resolution in which
+//! they are carried; [`rssi_dbm()`], [`rssi_code()`], [`snr_db()`] and [`snr_code()`] convert
+//! between those and the coded form.
+
+use super::beacon::channel;
+use super::{AnyIeType, SerializeIe, flag};
+use crate::{ParsingError, ValueRangeError};
+use ts_103_636_numbers as numbers;
+
+/// Converts a coded RSSI value into dBm.
+///
+/// The field carries the negated RSSI in dBm, covering 0 to -255 dBm.
+#[must_use]
+pub fn rssi_dbm(code: u8) -> i16 {
+    -i16::from(code)
+}
+
+/// Converts an RSSI value in dBm into its coded form, saturating at the ends of the range.
+#[must_use]
+pub fn rssi_code(dbm: i16) -> u8 {
+    u8::try_from(dbm.saturating_neg()).unwrap_or(if dbm > 0 { 0 } else { u8::MAX })
+}
+
+/// Converts a coded SNR value into dB.
+///
+/// The field carries the SNR in dB as a signed 8-bit number.
+#[must_use]
+pub fn snr_db(code: u8) -> i8 {
+    i8::from_be_bytes([code])
+}
+
+/// Converts an SNR value in dB into its coded form.
+#[must_use]
+pub fn snr_code(db: i8) -> u8 {
+    db.to_be_bytes()[0]
+}
+
+fn check_percentage(percentage: u8) -> Result<u8, ValueRangeError> {
+    if percentage > 100 {
+        return Err(ValueRangeError);
+    }
+    Ok(percentage)
+}
+
+/// Length of a Neighbouring payload as indicated by its leading flags.
+///
+/// Returns `None` if the data is too short to tell.
+pub(super) fn neighbouring_length(data: &[u8]) -> Option<usize> {
+    let flags = *data.first()?;
+    Some(
+        2 + 4 * flag(flags, 6)
+            + flag(flags, 5)
+            + flag(flags, 4)
+            + flag(flags, 3)
+            + 2 * flag(flags, 1)
+            + 4 * flag(flags, 0),
+    )
+}
+
+/// View on a Neighbouring message.
+///
+/// An FT describes through this another FT it has detected.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Neighbouring<'buf>(&'buf [u8]);
+
+impl<'buf> Neighbouring<'buf> {
+    /// Interprets the payload of a Neighbouring IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the one announced by its flags.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if neighbouring_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        Ok(Self(payload))
+    }
+
+    fn flags(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// Offset of an optional field, given the flag bits and lengths of the fields preceding it.
+    fn offset(&self, fields: &[(u8, usize)]) -> usize {
+        2 + fields
+            .iter()
+            .map(|(bit, len)| flag(self.flags(), *bit) * len)
+            .sum::<usize>()
+    }
+
+    /// The Power Const flag: Whether the neighbour operates under power constraints.
+    #[must_use]
+    pub fn power_constraints(&self) -> bool {
+        flag(self.flags(), 2) != 0
+    }
+
+    /// The neighbour's coded Network Beacon Period; see
+    /// [`network_beacon_period_ms()`][super::beacon::network_beacon_period_ms].
+    #[must_use]
+    pub fn network_beacon_period(&self) -> u8 {
+        self.0[1] >> 4
+    }
+
+    /// The neighbour's coded Cluster Beacon Period; see
+    /// [`cluster_beacon_period_ms()`][super::beacon::cluster_beacon_period_ms].
+    #[must_use]
+    pub fn cluster_beacon_period(&self) -> u8 {
+        self.0[1] & 0x0f
+    }
+
+    /// The neighbour's long RD ID, if given.
+    #[must_use]
+    pub fn long_rd_id(&self) -> Option<u32> {
+        (flag(self.flags(), 6) != 0)
+            .then(|| u32::from_be_bytes([self.0[2], self.0[3], self.0[4], self.0[5]]))
+    }
+
+    /// The channel of the neighbour's next cluster beacon, if given.
+    #[must_use]
+    pub fn next_cluster_channel(&self) -> Option<u16> {
+        let offset = self.offset(&[(6, 4)]);
+        (flag(self.flags(), 1) != 0).then(|| channel(&self.0[offset..]))
+    }
+
+    /// Time to the neighbour's next cluster beacon, in µs, if given.
+    #[must_use]
+    pub fn time_to_next(&self) -> Option<u32> {
+        let offset = self.offset(&[(6, 4), (1, 2)]);
+        (flag(self.flags(), 0) != 0).then(|| {
+            u32::from_be_bytes([
+                self.0[offset],
+                self.0[offset + 1],
+                self.0[offset + 2],
+                self.0[offset + 3],
+            ])
+        })
+    }
+
+    /// The RSSI-2 at which the neighbour is received, in dBm, if given.
+    #[must_use]
+    pub fn rssi_2(&self) -> Option<i16> {
+        let offset = self.offset(&[(6, 4), (1, 2), (0, 4)]);
+        (flag(self.flags(), 3) != 0).then(|| rssi_dbm(self.0[offset]))
+    }
+
+    /// The SNR at which the neighbour is received, in dB, if given.
+    #[must_use]
+    pub fn snr(&self) -> Option<i8> {
+        let offset = self.offset(&[(6, 4), (1, 2), (0, 4), (3, 1)]);
+        (flag(self.flags(), 4) != 0).then(|| snr_db(self.0[offset]))
+    }
+
+    /// The coded µ and β the neighbour operates with, if given.
+    ///
+    /// See [`subcarrier_scaling_factor()`][super::capability::subcarrier_scaling_factor] and
+    /// [`fourier_transform_scaling_factor()`][super::capability::fourier_transform_scaling_factor]
+    /// for their values.
+    #[must_use]
+    pub fn radio_device_class(&self) -> Option<(u8, u8)> {
+        let offset = self.offset(&[(6, 4), (1, 2), (0, 4), (3, 1), (4, 1)]);
+        (flag(self.flags(), 5) != 0).then(|| ((self.0[offset] >> 4) & 0x07, self.0[offset] & 0x0f))
+    }
+}
+
+/// Builder for a Neighbouring message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NeighbouringBuilder {
+    power_constraints: bool,
+    network_beacon_period: u8,
+    cluster_beacon_period: u8,
+    long_rd_id: Option<u32>,
+    next_cluster_channel: Option<u16>,
+    time_to_next: Option<u32>,
+    rssi_2: Option<u8>,
+    snr: Option<u8>,
+    radio_device_class: Option<u8>,
+}
+
+impl NeighbouringBuilder {
+    /// Creates a Neighbouring message with the neighbour's coded beacon periods.
+    ///
+    /// # Errors
+    ///
+    /// This errs if a period code exceeds 4 bits.
+    pub fn new(
+        network_beacon_period: u8,
+        cluster_beacon_period: u8,
+    ) -> Result<Self, ValueRangeError> {
+        if network_beacon_period > 0x0f || cluster_beacon_period > 0x0f {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            power_constraints: false,
+            network_beacon_period,
+            cluster_beacon_period,
+            long_rd_id: None,
+            next_cluster_channel: None,
+            time_to_next: None,
+            rssi_2: None,
+            snr: None,
+            radio_device_class: None,
+        })
+    }
+
+    /// Sets the Power Const flag.
+    #[must_use]
+    pub fn power_constraints(mut self, power_constraints: bool) -> Self {
+        self.power_constraints = power_constraints;
+        self
+    }
+
+    /// Sets the neighbour's long RD ID.
+    #[must_use]
+    pub fn long_rd_id(mut self, long_rd_id: u32) -> Self {
+        self.long_rd_id = Some(long_rd_id);
+        self
+    }
+
+    /// Sets the channel of the neighbour's next cluster beacon.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the channel exceeds 13 bits.
+    pub fn next_cluster_channel(mut self, channel: u16) -> Result<Self, ValueRangeError> {
+        if channel > 0x1fff {
+            return Err(ValueRangeError);
+        }
+        self.next_cluster_channel = Some(channel);
+        Ok(self)
+    }
+
+    /// Sets the time to the neighbour's next cluster beacon in µs.
+    #[must_use]
+    pub fn time_to_next(mut self, time_to_next: u32) -> Self {
+        self.time_to_next = Some(time_to_next);
+        self
+    }
+
+    /// Sets the RSSI-2 in dBm at which the neighbour is received.
+    ///
+    /// Values outside the expressible range are saturated.
+    #[must_use]
+    pub fn rssi_2(mut self, dbm: i16) -> Self {
+        self.rssi_2 = Some(rssi_code(dbm));
+        self
+    }
+
+    /// Sets the SNR in dB at which the neighbour is received.
+    #[must_use]
+    pub fn snr(mut self, db: i8) -> Self {
+        self.snr = Some(snr_code(db));
+        self
+    }
+
+    /// Sets the coded µ and β the neighbour operates with.
+    ///
+    /// # Errors
+    ///
+    /// This errs if µ exceeds 3 bits or β exceeds 4 bits.
+    pub fn radio_device_class(mut self, mu: u8, beta: u8) -> Result<Self, ValueRangeError> {
+        if mu > 0x07 || beta > 0x0f {
+            return Err(ValueRangeError);
+        }
+        self.radio_device_class = Some((mu << 4) | beta);
+        Ok(self)
+    }
+}
+
+impl SerializeIe for NeighbouringBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::NEIGHBOURING);
+
+    fn payload_len(&self) -> usize {
+        2 + 4 * usize::from(self.long_rd_id.is_some())
+            + 2 * usize::from(self.next_cluster_channel.is_some())
+            + 4 * usize::from(self.time_to_next.is_some())
+            + usize::from(self.rssi_2.is_some())
+            + usize::from(self.snr.is_some())
+            + usize::from(self.radio_device_class.is_some())
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[
+            (u8::from(self.long_rd_id.is_some()) << 6)
+                | (u8::from(self.radio_device_class.is_some()) << 5)
+                | (u8::from(self.snr.is_some()) << 4)
+                | (u8::from(self.rssi_2.is_some()) << 3)
+                | (u8::from(self.power_constraints) << 2)
+                | (u8::from(self.next_cluster_channel.is_some()) << 1)
+                | u8::from(self.time_to_next.is_some()),
+            (self.network_beacon_period << 4) | self.cluster_beacon_period,
+        ])?;
+        if let Some(long_rd_id) = self.long_rd_id {
+            w.write_all(&long_rd_id.to_be_bytes())?;
+        }
+        if let Some(channel) = self.next_cluster_channel {
+            w.write_all(&channel.to_be_bytes())?;
+        }
+        if let Some(time_to_next) = self.time_to_next {
+            w.write_all(&time_to_next.to_be_bytes())?;
+        }
+        for byte in [self.rssi_2, self.snr, self.radio_device_class]
+            .into_iter()
+            .flatten()
+        {
+            w.write_all(&[byte])?;
+        }
+        Ok(())
+    }
+}
+
+/// Length of a Load Info payload as indicated by its leading flags.
+///
+/// Returns `None` if the data is too short to tell.
+pub(super) fn load_info_length(data: &[u8]) -> Option<usize> {
+    let flags = *data.first()?;
+    Some(4 + flag(flags, 3) + flag(flags, 2) + flag(flags, 1) + 2 * flag(flags, 0))
+}
+
+/// View on a Load Info message.
+///
+/// An FT describes through this how busy it and its channel are.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LoadInfo<'buf>(&'buf [u8]);
+
+impl<'buf> LoadInfo<'buf> {
+    /// Interprets the payload of a Load Info IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the one announced by its flags.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if load_info_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        Ok(Self(payload))
+    }
+
+    fn flags(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// Offset of the percentage of associated RDs in FT mode.
+    fn ft_offset(&self) -> usize {
+        3 + flag(self.flags(), 3)
+    }
+
+    /// Percentage of the FT's traffic capacity that is in use.
+    #[must_use]
+    pub fn traffic_load(&self) -> u8 {
+        self.0[1]
+    }
+
+    /// Maximum number of RDs that can associate with the FT.
+    #[must_use]
+    pub fn max_associated(&self) -> u16 {
+        if flag(self.flags(), 3) == 0 {
+            u16::from(self.0[2])
+        } else {
+            u16::from_be_bytes([self.0[2], self.0[3]])
+        }
+    }
+
+    /// Percentage of the associated RDs that operate in FT mode.
+    #[must_use]
+    pub fn associated_ft_mode(&self) -> u8 {
+        self.0[self.ft_offset()]
+    }
+
+    /// Percentage of the associated RDs that operate in PT mode, if given.
+    #[must_use]
+    pub fn associated_pt_mode(&self) -> Option<u8> {
+        (flag(self.flags(), 2) != 0).then(|| self.0[self.ft_offset() + 1])
+    }
+
+    /// Percentage of the random access resources that are in use, if given.
+    #[must_use]
+    pub fn rach_load(&self) -> Option<u8> {
+        let offset = self.ft_offset() + 1 + flag(self.flags(), 2);
+        (flag(self.flags(), 1) != 0).then(|| self.0[offset])
+    }
+
+    /// Percentages of subslots that were detected as free and busy, respectively, if given.
+    #[must_use]
+    pub fn channel_load(&self) -> Option<(u8, u8)> {
+        let offset = self.ft_offset() + 1 + flag(self.flags(), 2) + flag(self.flags(), 1);
+        (flag(self.flags(), 0) != 0).then(|| (self.0[offset], self.0[offset + 1]))
+    }
+}
+
+/// Builder for a Load Info message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LoadInfoBuilder {
+    traffic_load: u8,
+    max_associated: u16,
+    associated_ft_mode: u8,
+    associated_pt_mode: Option<u8>,
+    rach_load: Option<u8>,
+    channel_load: Option<(u8, u8)>,
+}
+
+impl LoadInfoBuilder {
+    /// Creates a Load Info message with its mandatory fields.
+    ///
+    /// The maximum number of associated RDs is sent in 8 bits if it fits, otherwise in 16.
+    ///
+    /// # Errors
+    ///
+    /// This errs if a percentage exceeds 100.
+    pub fn new(
+        traffic_load: u8,
+        max_associated: u16,
+        associated_ft_mode: u8,
+    ) -> Result<Self, ValueRangeError> {
+        Ok(Self {
+            traffic_load: check_percentage(traffic_load)?,
+            max_associated,
+            associated_ft_mode: check_percentage(associated_ft_mode)?,
+            associated_pt_mode: None,
+            rach_load: None,
+            channel_load: None,
+        })
+    }
+
+    /// Sets the percentage of associated RDs that operate in PT mode.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the percentage exceeds 100.
+    pub fn associated_pt_mode(mut self, percentage: u8) -> Result<Self, ValueRangeError> {
+        self.associated_pt_mode = Some(check_percentage(percentage)?);
+        Ok(self)
+    }
+
+    /// Sets the percentage of random access resources in use.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the percentage exceeds 100.
+    pub fn rach_load(mut self, percentage: u8) -> Result<Self, ValueRangeError> {
+        self.rach_load = Some(check_percentage(percentage)?);
+        Ok(self)
+    }
+
+    /// Sets the percentages of subslots detected as free and busy.
+    ///
+    /// # Errors
+    ///
+    /// This errs if a percentage exceeds 100.
+    pub fn channel_load(mut self, free: u8, busy: u8) -> Result<Self, ValueRangeError> {
+        self.channel_load = Some((check_percentage(free)?, check_percentage(busy)?));
+        Ok(self)
+    }
+
+    fn max_associated_is_long(&self) -> bool {
+        self.max_associated > u8::MAX.into()
+    }
+}
+
+impl SerializeIe for LoadInfoBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::LOAD_INFO);
+
+    fn payload_len(&self) -> usize {
+        4 + usize::from(self.max_associated_is_long())
+            + usize::from(self.associated_pt_mode.is_some())
+            + usize::from(self.rach_load.is_some())
+            + 2 * usize::from(self.channel_load.is_some())
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[
+            (u8::from(self.max_associated_is_long()) << 3)
+                | (u8::from(self.associated_pt_mode.is_some()) << 2)
+                | (u8::from(self.rach_load.is_some()) << 1)
+                | u8::from(self.channel_load.is_some()),
+            self.traffic_load,
+        ])?;
+        let max_associated = self.max_associated.to_be_bytes();
+        if self.max_associated_is_long() {
+            w.write_all(&max_associated)?;
+        } else {
+            w.write_all(&max_associated[1..])?;
+        }
+        w.write_all(&[self.associated_ft_mode])?;
+        for byte in [self.associated_pt_mode, self.rach_load]
+            .into_iter()
+            .flatten()
+        {
+            w.write_all(&[byte])?;
+        }
+        if let Some((free, busy)) = self.channel_load {
+            w.write_all(&[free, busy])?;
+        }
+        Ok(())
+    }
+}
+
+/// Length of a Measurement Report payload as indicated by its leading flags.
+///
+/// Returns `None` if the data is too short to tell.
+pub(super) fn measurement_report_length(data: &[u8]) -> Option<usize> {
+    let flags = *data.first()?;
+    Some(1 + flag(flags, 4) + flag(flags, 3) + flag(flags, 2) + flag(flags, 1))
+}
+
+/// View on a Measurement Report message.
+///
+/// A PT reports through this the quality at which it receives its FT.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MeasurementReport<'buf>(&'buf [u8]);
+
+impl<'buf> MeasurementReport<'buf> {
+    /// Interprets the payload of a Measurement Report IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the one announced by its flags.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if measurement_report_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        Ok(Self(payload))
+    }
+
+    fn flags(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// Offset of an optional field, given the flag bits and lengths of the fields preceding it.
+    fn offset(&self, fields: &[(u8, usize)]) -> usize {
+        1 + fields
+            .iter()
+            .map(|(bit, len)| flag(self.flags(), *bit) * len)
+            .sum::<usize>()
+    }
+
+    /// The reported SNR in dB, if given.
+    #[must_use]
+    pub fn snr(&self) -> Option<i8> {
+        let offset = self.offset(&[]);
+        (flag(self.flags(), 4) != 0).then(|| snr_db(self.0[offset]))
+    }
+
+    /// The reported RSSI-2 in dBm, if given.
+    #[must_use]
+    pub fn rssi_2(&self) -> Option<i16> {
+        let offset = self.offset(&[(4, 1)]);
+        (flag(self.flags(), 3) != 0).then(|| rssi_dbm(self.0[offset]))
+    }
+
+    /// The reported RSSI-1 in dBm, if given.
+    #[must_use]
+    pub fn rssi_1(&self) -> Option<i16> {
+        let offset = self.offset(&[(4, 1), (3, 1)]);
+        (flag(self.flags(), 2) != 0).then(|| rssi_dbm(self.0[offset]))
+    }
+
+    /// The number of transmissions (including retransmissions) of the last HARQ process, if
+    /// given.
+    #[must_use]
+    pub fn tx_count(&self) -> Option<u8> {
+        let offset = self.offset(&[(4, 1), (3, 1), (2, 1)]);
+        (flag(self.flags(), 1) != 0).then(|| self.0[offset])
+    }
+
+    /// Whether the measurements were made on a random access resource (rather than on a
+    /// scheduled one or a beacon).
+    #[must_use]
+    pub fn rach(&self) -> bool {
+        flag(self.flags(), 0) != 0
+    }
+}
+
+/// Builder for a Measurement Report message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MeasurementReportBuilder {
+    snr: Option<u8>,
+    rssi_2: Option<u8>,
+    rssi_1: Option<u8>,
+    tx_count: Option<u8>,
+    rach: bool,
+}
+
+impl MeasurementReportBuilder {
+    /// Creates an empty Measurement Report, indicating whether the measurements are from a
+    /// random access resource.
+    #[must_use]
+    pub fn new(rach: bool) -> Self {
+        Self {
+            snr: None,
+            rssi_2: None,
+            rssi_1: None,
+            tx_count: None,
+            rach,
+        }
+    }
+
+    /// Sets the SNR in dB.
+    #[must_use]
+    pub fn snr(mut self, db: i8) -> Self {
+        self.snr = Some(snr_code(db));
+        self
+    }
+
+    /// Sets the RSSI-2 in dBm.
+    ///
+    /// Values outside the expressible range are saturated.
+    #[must_use]
+    pub fn rssi_2(mut self, dbm: i16) -> Self {
+        self.rssi_2 = Some(rssi_code(dbm));
+        self
+    }
+
+    /// Sets the RSSI-1 in dBm.
+    ///
+    /// Values outside the expressible range are saturated.
+    #[must_use]
+    pub fn rssi_1(mut self, dbm: i16) -> Self {
+        self.rssi_1 = Some(rssi_code(dbm));
+        self
+    }
+
+    /// Sets the number of transmissions of the last HARQ process.
+    #[must_use]
+    pub fn tx_count(mut self, tx_count: u8) -> Self {
+        self.tx_count = Some(tx_count);
+        self
+    }
+}
+
+impl SerializeIe for MeasurementReportBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::MEASUREMENT_REPORT);
+
+    fn payload_len(&self) -> usize {
+        1 + [self.snr, self.rssi_2, self.rssi_1, self.tx_count]
+            .iter()
+            .flatten()
+            .count()
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[(u8::from(self.snr.is_some()) << 4)
+            | (u8::from(self.rssi_2.is_some()) << 3)
+            | (u8::from(self.rssi_1.is_some()) << 2)
+            | (u8::from(self.tx_count.is_some()) << 1)
+            | u8::from(self.rach)])?;
+        for byte in [self.snr, self.rssi_2, self.rssi_1, self.tx_count]
+            .into_iter()
+            .flatten()
+        {
+            w.write_all(&[byte])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(rssi_dbm(rssi_code(-85)), -85);
+        assert_eq!(rssi_code(-300), 255);
+        assert_eq!(rssi_code(10), 0);
+        assert_eq!(rssi_code(i16::MIN), 255);
+        assert_eq!(snr_db(snr_code(-5)), -5);
+        assert_eq!(snr_code(-5), 0xfb);
+    }
+
+    #[test]
+    fn test_neighbouring() {
+        let builder = NeighbouringBuilder::new(1, 2)
+            .unwrap()
+            .long_rd_id(0x1234_5678)
+            .time_to_next(50_000)
+            .rssi_2(-70)
+            .snr(12)
+            .radio_device_class(0, 0)
+            .unwrap();
+        let mut written = [0; 15];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(
+            written,
+            [
+                0x40 | 0x15,
+                13,
+                0x79,
+                0x12,
+                0x12,
+                0x34,
+                0x56,
+                0x78,
+                0x00,
+                0x00,
+                0xc3,
+                0x50,
+                70,
+                12,
+                0x00
+            ]
+        );
+        let neighbour = Neighbouring::parse(&written[2..]).unwrap();
+        assert!(!neighbour.power_constraints());
+        assert_eq!(neighbour.network_beacon_period(), 1);
+        assert_eq!(neighbour.cluster_beacon_period(), 2);
+        assert_eq!(neighbour.long_rd_id(), Some(0x1234_5678));
+        assert_eq!(neighbour.next_cluster_channel(), None);
+        assert_eq!(neighbour.time_to_next(), Some(50_000));
+        assert_eq!(neighbour.rssi_2(), Some(-70));
+        assert_eq!(neighbour.snr(), Some(12));
+        assert_eq!(neighbour.radio_device_class(), Some((0, 0)));
+
+        let builder = NeighbouringBuilder::new(1, 2)
+            .unwrap()
+            .power_constraints(true)
+            .next_cluster_channel(1667)
+            .unwrap()
+            .snr(-3);
+        let mut written = [0; 5];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        let neighbour = Neighbouring::parse(&written).unwrap();
+        assert!(neighbour.power_constraints());
+        assert_eq!(neighbour.long_rd_id(), None);
+        assert_eq!(neighbour.next_cluster_channel(), Some(1667));
+        assert_eq!(neighbour.rssi_2(), None);
+        assert_eq!(neighbour.snr(), Some(-3));
+        Neighbouring::parse(&written[..4]).unwrap_err();
+    }
+
+    #[test]
+    fn test_load_info() {
+        let builder = LoadInfoBuilder::new(40, 8, 0).unwrap();
+        let mut written = [0; 6];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(written, [0x40 | 0x18, 4, 0x00, 40, 8, 0]);
+        let load = LoadInfo::parse(&written[2..]).unwrap();
+        assert_eq!(load.traffic_load(), 40);
+        assert_eq!(load.max_associated(), 8);
+        assert_eq!(load.associated_ft_mode(), 0);
+        assert_eq!(load.associated_pt_mode(), None);
+        assert_eq!(load.channel_load(), None);
+
+        let builder = LoadInfoBuilder::new(40, 1000, 10)
+            .unwrap()
+            .associated_pt_mode(90)
+            .unwrap()
+            .rach_load(5)
+            .unwrap()
+            .channel_load(70, 30)
+            .unwrap();
+        let mut written = [0; 9];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        assert_eq!(builder.payload_len(), 9);
+        let load = LoadInfo::parse(&written).unwrap();
+        assert_eq!(load.max_associated(), 1000);
+        assert_eq!(load.associated_ft_mode(), 10);
+        assert_eq!(load.associated_pt_mode(), Some(90));
+        assert_eq!(load.rach_load(), Some(5));
+        assert_eq!(load.channel_load(), Some((70, 30)));
+
+        LoadInfoBuilder::new(101, 0, 0).unwrap_err();
+    }
+
+    #[test]
+    fn test_measurement_report() {
+        let builder = MeasurementReportBuilder::new(false)
+            .snr(20)
+            .rssi_1(-90)
+            .tx_count(2);
+        let mut written = [0; 6];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(written, [0x40 | 0x19, 4, 0x16, 20, 90, 2]);
+        let report = MeasurementReport::parse(&written[2..]).unwrap();
+        assert_eq!(report.snr(), Some(20));
+        assert_eq!(report.rssi_2(), None);
+        assert_eq!(report.rssi_1(), Some(-90));
+        assert_eq!(report.tx_count(), Some(2));
+        assert!(!report.rach());
+
+        let report = MeasurementReport::parse(&[0x09, 60]).unwrap();
+        assert_eq!(report.rssi_2(), Some(-60));
+        assert!(report.rach());
+        MeasurementReport::parse(&[0x09]).unwrap_err();
+    }
+}
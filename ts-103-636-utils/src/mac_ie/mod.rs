@@ -5,6 +5,78 @@
 use super::{InputLengthError, ParsingError};
 use ts_103_636_numbers as numbers;
 
+/// Defines an enum for a coded field whose values are all listed in the specification, with
+/// conversions from and to the field's value.
+macro_rules! coded_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $code:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        #[repr(u8)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant = $code,)*
+        }
+
+        impl $name {
+            /// Decodes the field's value; returns `None` for reserved values.
+            #[must_use]
+            pub fn from_code(code: u8) -> Option<Self> {
+                match code {
+                    $($code => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// The field's value.
+            #[must_use]
+            pub fn code(self) -> u8 {
+                self as u8
+            }
+        }
+    };
+}
+
+/// Reads a bit of a flags byte as 0 or 1, which can be multiplied with the length of the optional
+/// field it indicates.
+pub(super) fn flag(byte: u8, bit: u8) -> usize {
+    usize::from((byte >> bit) & 1)
+}
+
+coded_enum! {
+    /// Value of the Repeat field of the IEs that describe a resource.
+    ///
+    /// The [Random Access Resource IE][random_access] only expresses the first three values.
+    pub enum Repeat {
+        /// The resource is available only once.
+        Single = 0,
+        /// The resource is repeated every Repetition frames.
+        Frames = 1,
+        /// The resource is repeated every Repetition subslots.
+        Subslots = 2,
+        /// Like [`Repeat::Frames`], and additional allocations may be added to it.
+        FramesAdditionsAllowed = 3,
+        /// Like [`Repeat::Subslots`], and additional allocations may be added to it.
+        SubslotsAdditionsAllowed = 4,
+    }
+}
+
+pub mod association;
+pub mod beacon;
+pub mod capability;
+pub mod joining;
+pub mod measurement;
+pub mod paging;
+pub mod random_access;
+pub mod resource_allocation;
+pub mod routing;
+pub mod security_info;
+pub mod short;
+
 /// A single IE of the MAC layer.
 ///
 /// This is mainly for parsing, but can also be used to serialize from owned values into a buffer.
@@ -143,6 +215,29 @@ impl<'a> InformationElement<'a> {
         Ok(Self { head, payload })
     }
 
+    /// Creates an IE out of a 6-bit MAC IE without length field (MAC_Ext 00).
+    ///
+    /// Only IEs whose length can be told from their type and content can be encoded this way (see
+    /// [`implicit_payload_length`]). A Padding IE encoded this way extends to the end of the MAC
+    /// PDU, so it is only suitable as the last IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the IE type does not define its length, or if the payload is inconsistent
+    /// with the length it defines.
+    pub fn new_6bit_implicit_length(
+        type_: numbers::mac_ie::IEType6bit,
+        payload: &'a [u8],
+    ) -> Result<Self, InputLengthError> {
+        if implicit_payload_length(type_, payload) != Some(payload.len()) {
+            return Err(InputLengthError);
+        }
+
+        let head = u8::from(type_) | (numbers::mac_pdu::mux_ext::NO_LENGTH_FIELD << 6);
+
+        Ok(Self { head, payload })
+    }
+
     /// Reads an IE out of a MAC IE stream.
     ///
     /// The success result is an item in that buffer; in that case, the input slice is shortened to
@@ -154,8 +249,9 @@ impl<'a> InformationElement<'a> {
     /// having enough data.
     ///
     /// A second case that can produce parsing errors are MAC_Ext 00 (IE type defines length)
-    /// values where the IE is not known; those too produce a paring error, as they have the same
-    /// effect of terminating processing prematurely and irrecoverably.
+    /// values where the IE's length can not be determined (see [`implicit_payload_length`]);
+    /// those too produce a paring error, as they have the same effect of terminating processing
+    /// prematurely and irrecoverably.
     ///
     /// On error, the input slice is still advanced, but not left in a position where further
     /// elements can be expected to be decoded.
@@ -166,15 +262,11 @@ impl<'a> InformationElement<'a> {
     pub fn parse<'buf>(data: &mut &'buf [u8]) -> Result<InformationElement<'buf>, ParsingError> {
         let head = *data.split_off_first().ok_or(ParsingError)?;
         let mac_ext = head >> 6;
-        let len: u16 = match mac_ext {
+        let len: usize = match mac_ext {
             numbers::mac_pdu::mux_ext::NO_LENGTH_FIELD => {
-                let ie_type = head & 0x3f;
-                let _ie_type = numbers::mac_ie::IEType6bit::try_from(ie_type).expect("Bits masked");
-                // FIXME: We won't just have to query the IE type, but also let the remaining
-                // length and the data in the IE influence it -- see
-                // <https://github.com/ariel-os/hophop/issues/11>
-                let len: Option<u16> = None;
-                len.ok_or(ParsingError)?
+                let ie_type =
+                    numbers::mac_ie::IEType6bit::try_from(head & 0x3f).expect("Bits masked");
+                implicit_payload_length(ie_type, data).ok_or(ParsingError)?
             }
             numbers::mac_pdu::mux_ext::LENGTH_8BIT => {
                 let len = *data.split_off_first().ok_or(ParsingError)?;
@@ -183,12 +275,12 @@ impl<'a> InformationElement<'a> {
             numbers::mac_pdu::mux_ext::LENGTH_16BIT => {
                 let high = *data.split_off_first().ok_or(ParsingError)?;
                 let low = *data.split_off_first().ok_or(ParsingError)?;
-                (u16::from(high) << 8) | u16::from(low)
+                u16::from_be_bytes([high, low]).into()
             }
             numbers::mac_pdu::mux_ext::SHORT_IE => ((head >> 5) & 1).into(),
             _ => unreachable!("Bit shift only admits those values"),
         };
-        let payload = data.split_off(..len as usize).ok_or(ParsingError)?;
+        let payload = data.split_off(..len).ok_or(ParsingError)?;
         Ok(InformationElement { head, payload })
     }
 
@@ -240,6 +332,17 @@ impl<'a> InformationElement<'a> {
         self.payload
     }
 
+    /// Length of the complete IE as written by [`Self::serialize()`].
+    #[must_use]
+    pub fn ie_len(&self) -> usize {
+        let header_len = match self.head >> 6 {
+            numbers::mac_pdu::mux_ext::LENGTH_8BIT => 2,
+            numbers::mac_pdu::mux_ext::LENGTH_16BIT => 3,
+            _ => 1,
+        };
+        header_len + self.payload.len()
+    }
+
     /// Serializes into any [`embedded_io::Write`]r.
     ///
     /// # Errors
@@ -274,8 +377,101 @@ impl<'a> InformationElement<'a> {
     }
 }
 
+/// An owned IE payload that knows its IE type, and can thus be serialized into a complete IE.
+///
+/// This is implemented by the builders of the typed IE modules (e.g.
+/// [`beacon::ClusterBeaconBuilder`]).
+pub trait SerializeIe {
+    /// The IE type announced in the IE header.
+    const IE_TYPE: AnyIeType;
+
+    /// Length of the payload, excluding the IE header.
+    fn payload_len(&self) -> usize;
+
+    /// Serializes only the payload into any [`embedded_io::Write`]r.
+    ///
+    /// # Errors
+    ///
+    /// This merely forwards any errors of the writer.
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error>;
+
+    /// Length of the complete IE as written by [`Self::serialize()`].
+    fn ie_len(&self) -> usize {
+        let header_len = match Self::IE_TYPE {
+            AnyIeType::Type6bit(_) if self.payload_len() <= u8::MAX.into() => 2,
+            AnyIeType::Type6bit(_) => 3,
+            AnyIeType::Type5bit(_) => 1,
+        };
+        header_len + self.payload_len()
+    }
+
+    /// Serializes the complete IE into any [`embedded_io::Write`]r.
+    ///
+    /// 6-bit IE types are sent with the shortest length field that fits the payload.
+    ///
+    /// # Errors
+    ///
+    /// This merely forwards any errors of the writer.
+    fn serialize<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        let len = self.payload_len();
+        match Self::IE_TYPE {
+            AnyIeType::Type6bit(type_) => {
+                if let Ok(len) = u8::try_from(len) {
+                    w.write_all(&[
+                        u8::from(type_) | (numbers::mac_pdu::mux_ext::LENGTH_8BIT << 6),
+                        len,
+                    ])?;
+                } else {
+                    let len = u16::try_from(len).expect("Typed IE payloads are never that long");
+                    w.write_all(&[
+                        u8::from(type_) | (numbers::mac_pdu::mux_ext::LENGTH_16BIT << 6)
+                    ])?;
+                    w.write_all(&len.to_be_bytes())?;
+                }
+            }
+            AnyIeType::Type5bit(type_) => {
+                debug_assert_eq!(len, type_.len(), "Payload length mismatches IE type");
+                w.write_all(&[type_.composite() | (numbers::mac_pdu::mux_ext::SHORT_IE << 6)])?;
+            }
+        }
+        self.serialize_payload(w)
+    }
+}
+
+/// Length of an IE payload sent with MAC_Ext 00 (no length field), where the IE type defines the
+/// length.
+///
+/// The `data` are everything that follows the IE header up to the end of the MAC PDU. For some IE
+/// types, the length is fixed; for others, it depends on flags inside the leading bytes of the
+/// payload. A Padding IE without length field consumes the rest of the MAC PDU (as Wireshark
+/// interprets it).
+///
+/// Returns `None` if the IE type does not define its length, or if the data is too short to even
+/// tell the length.
+#[must_use]
+pub fn implicit_payload_length(ie_type: numbers::mac_ie::IEType6bit, data: &[u8]) -> Option<usize> {
+    use numbers::mac_ie::ie6bit;
+
+    Some(match ie_type {
+        ie6bit::PADDING => data.len(),
+        ie6bit::NETWORK_BEACON => beacon::network_beacon_length(data)?,
+        ie6bit::CLUSTER_BEACON => beacon::cluster_beacon_length(data)?,
+        ie6bit::ASSOCIATION_RELEASE => 1,
+        ie6bit::MAC_SECURITY_INFO => 5,
+        ie6bit::ROUTE_INFO => 6,
+        ie6bit::RANDOM_ACCESS_RESOURCE => random_access::random_access_resource_length(data)?,
+        ie6bit::BROADCAST_INDICATION => paging::broadcast_indication_length(data)?,
+        ie6bit::NEIGHBOURING => measurement::neighbouring_length(data)?,
+        ie6bit::LOAD_INFO => measurement::load_info_length(data)?,
+        ie6bit::MEASUREMENT_REPORT => measurement::measurement_report_length(data)?,
+        _ => return None,
+    })
+}
+
 #[cfg(test)]
 mod test {
+    use ts_103_636_numbers as numbers;
+
     /// A test across both the parser and the formatting.
     ///
     /// The output strings are not set in stone, but conveniently serve as a check that the data is
@@ -301,4 +497,48 @@ mod test {
 
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_parse_implicit_length() {
+        extern crate alloc;
+        use alloc::vec::Vec;
+
+        use crate::mac_ie::InformationElement;
+        use numbers::mac_ie::ie6bit;
+
+        // The IEs of the above test, but encoded without length fields
+        let data = [
+            9, 176, 16, 6, 0, 13, 19, 8, 12, 138, 160, 215, 2, 100, 0, 0, 0, 0, 0, 0,
+        ];
+        let parsed: Vec<_> = InformationElement::parse_stream(&data)
+            .map(|i| i.unwrap())
+            .collect();
+        assert_eq!(parsed.len(), 3);
+        assert!(parsed[0].ie_number() == ie6bit::CLUSTER_BEACON);
+        assert_eq!(parsed[0].payload(), [176, 16, 6, 0, 13]);
+        assert!(parsed[1].ie_number() == ie6bit::RANDOM_ACCESS_RESOURCE);
+        assert_eq!(parsed[1].payload(), [8, 12, 138, 160, 215, 2, 100]);
+        assert!(parsed[2].ie_number() == ie6bit::PADDING);
+        assert_eq!(parsed[2].payload(), [0; 5]);
+
+        let mut written = [0xff; 20];
+        let mut writer = &mut written[..];
+        for ie in &parsed {
+            InformationElement::new_6bit_implicit_length(
+                numbers::mac_ie::IEType6bit::try_from(ie.head & 0x3f).unwrap(),
+                ie.payload(),
+            )
+            .unwrap()
+            .serialize(&mut writer)
+            .unwrap();
+        }
+        assert_eq!(written, data);
+
+        // Cluster beacon whose flags announce a "Next Cluster Channel" that is missing
+        InformationElement::parse(&mut &[9, 176, 18, 6, 0, 13][..]).unwrap_err();
+        InformationElement::new_6bit_implicit_length(ie6bit::CLUSTER_BEACON, &[176, 18, 6, 0, 13])
+            .unwrap_err();
+        // Group assignment does not define its length
+        InformationElement::parse(&mut &[23, 0, 0][..]).unwrap_err();
+    }
 }
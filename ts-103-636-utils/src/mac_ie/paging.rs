@@ -0,0 +1,420 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Broadcast Indication and Group Assignment messages
+//!
+//! These are the payloads of the
+//! [`BROADCAST_INDICATION`][numbers::mac_ie::ie6bit::BROADCAST_INDICATION] and
+//! [`GROUP_ASSIGNMENT`][numbers::mac_ie::ie6bit::GROUP_ASSIGNMENT] IEs as defined in Sections
+//! 6.4.3.7 and 6.4.3.9 of ETSI TS 103 636-4 V2.1.1, through which an FT pages PTs in power saving
+//! mode and assigns group scheduled resources to them.
+
+use super::{AnyIeType, SerializeIe};
+use crate::{ParsingError, ValueRangeError};
+use ts_103_636_numbers as numbers;
+
+/// Number of resource tags a [`GroupAssignmentBuilder`] can hold.
+const MAX_RESOURCE_TAGS: usize = 8;
+
+coded_enum! {
+    /// Indication Type of a Broadcast Indication message.
+    pub enum IndicationType {
+        /// The indicated RD is paged.
+        Paging = 0,
+        /// The message is a response to a transmission on a random access resource.
+        RandomAccessResponse = 1,
+    }
+}
+
+coded_enum! {
+    /// Kind of feedback included in a Broadcast Indication message.
+    pub enum FeedbackType {
+        /// Channel quality, as a coded 4-bit MCS.
+        ChannelQuality = 1,
+        /// MIMO feedback for 2 antennas.
+        Mimo2Antennas = 2,
+        /// MIMO feedback for 4 antennas.
+        Mimo4Antennas = 3,
+    }
+}
+
+/// An RD ID of either length.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RdId {
+    /// A 16-bit short RD ID.
+    Short(u16),
+    /// A 32-bit long RD ID.
+    Long(u32),
+}
+
+/// Length of a Broadcast Indication payload as indicated by its leading flags.
+///
+/// Returns `None` if the data is too short to tell.
+pub(super) fn broadcast_indication_length(data: &[u8]) -> Option<usize> {
+    let flags = *data.first()?;
+    let id = if (flags >> 4) & 1 == 0 { 2 } else { 4 };
+    let feedback = usize::from((flags >> 1) & 0x03 != 0);
+    Some(1 + id + feedback)
+}
+
+/// View on a Broadcast Indication message.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BroadcastIndication<'buf>(&'buf [u8]);
+
+impl<'buf> BroadcastIndication<'buf> {
+    /// Interprets the payload of a Broadcast Indication IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the one announced by its flags, or if the
+    /// indication type has a reserved value.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if broadcast_indication_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        IndicationType::from_code(payload[0] >> 5).ok_or(ParsingError)?;
+        Ok(Self(payload))
+    }
+
+    /// What the message indicates.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "Reserved values were excluded at parsing"
+    )]
+    #[must_use]
+    pub fn indication_type(&self) -> IndicationType {
+        IndicationType::from_code(self.0[0] >> 5).expect("Checked at parsing")
+    }
+
+    /// The RD the message is addressed to.
+    #[must_use]
+    pub fn rd_id(&self) -> RdId {
+        if (self.0[0] >> 4) & 1 == 0 {
+            RdId::Short(u16::from_be_bytes([self.0[1], self.0[2]]))
+        } else {
+            RdId::Long(u32::from_be_bytes([
+                self.0[1], self.0[2], self.0[3], self.0[4],
+            ]))
+        }
+    }
+
+    /// The ACK/NACK flag: In a random access response, whether the RD's transmission was
+    /// received successfully.
+    #[must_use]
+    pub fn ack(&self) -> bool {
+        (self.0[0] >> 3) & 1 != 0
+    }
+
+    /// The kind of included feedback and its coded value, if any.
+    #[expect(clippy::missing_panics_doc, reason = "Length was checked at parsing")]
+    #[must_use]
+    pub fn feedback(&self) -> Option<(FeedbackType, u8)> {
+        let feedback_type = FeedbackType::from_code((self.0[0] >> 1) & 0x03)?;
+        Some((
+            feedback_type,
+            *self.0.last().expect("Length checked at parsing"),
+        ))
+    }
+
+    /// Whether a Resource Allocation IE in the same MAC PDU assigns resources to the RD.
+    #[must_use]
+    pub fn resource_allocation(&self) -> bool {
+        self.0[0] & 1 != 0
+    }
+
+    /// Whether this message pages the RD with the given IDs.
+    ///
+    /// Depending on which ID the message carries, either the long or the short RD ID is compared.
+    #[must_use]
+    pub fn pages(&self, long_rd_id: u32, short_rd_id: u16) -> bool {
+        self.indication_type() == IndicationType::Paging
+            && match self.rd_id() {
+                RdId::Short(id) => id == short_rd_id,
+                RdId::Long(id) => id == long_rd_id,
+            }
+    }
+}
+
+/// Builder for a Broadcast Indication message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BroadcastIndicationBuilder {
+    indication_type: IndicationType,
+    rd_id: RdId,
+    ack: bool,
+    feedback: Option<(FeedbackType, u8)>,
+    resource_allocation: bool,
+}
+
+impl BroadcastIndicationBuilder {
+    /// Creates a message that pages the given RD.
+    #[must_use]
+    pub fn paging(rd_id: RdId) -> Self {
+        Self {
+            indication_type: IndicationType::Paging,
+            rd_id,
+            ack: false,
+            feedback: None,
+            resource_allocation: false,
+        }
+    }
+
+    /// Creates a message that responds to the given RD's transmission on a random access
+    /// resource.
+    #[must_use]
+    pub fn random_access_response(rd_id: RdId, ack: bool) -> Self {
+        Self {
+            indication_type: IndicationType::RandomAccessResponse,
+            ack,
+            ..Self::paging(rd_id)
+        }
+    }
+
+    /// Includes feedback of the given kind with its coded value.
+    #[must_use]
+    pub fn feedback(mut self, feedback_type: FeedbackType, value: u8) -> Self {
+        self.feedback = Some((feedback_type, value));
+        self
+    }
+
+    /// Sets whether a Resource Allocation IE in the same MAC PDU assigns resources to the RD.
+    #[must_use]
+    pub fn resource_allocation(mut self, resource_allocation: bool) -> Self {
+        self.resource_allocation = resource_allocation;
+        self
+    }
+}
+
+impl SerializeIe for BroadcastIndicationBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::BROADCAST_INDICATION);
+
+    fn payload_len(&self) -> usize {
+        let id = match self.rd_id {
+            RdId::Short(_) => 2,
+            RdId::Long(_) => 4,
+        };
+        1 + id + usize::from(self.feedback.is_some())
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[(self.indication_type.code() << 5)
+            | (u8::from(matches!(self.rd_id, RdId::Long(_))) << 4)
+            | (u8::from(self.ack) << 3)
+            | (self.feedback.map_or(0, |(t, _)| t.code()) << 1)
+            | u8::from(self.resource_allocation)])?;
+        match self.rd_id {
+            RdId::Short(id) => w.write_all(&id.to_be_bytes())?,
+            RdId::Long(id) => w.write_all(&id.to_be_bytes())?,
+        }
+        if let Some((_, value)) = self.feedback {
+            w.write_all(&[value])?;
+        }
+        Ok(())
+    }
+}
+
+/// A resource tag in a Group Assignment message.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResourceTag {
+    /// The Direct flag: Whether the tagged resource is assigned directly to the RD, rather than
+    /// being shared by the group.
+    pub direct: bool,
+    /// The 7-bit tag of the resource within the group.
+    pub tag: u8,
+}
+
+/// View on a Group Assignment message.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GroupAssignment<'buf>(&'buf [u8]);
+
+impl<'buf> GroupAssignment<'buf> {
+    /// Interprets the payload of a Group Assignment IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload contains no resource tags, or more than one while the Single flag
+    /// is set.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        match (payload.first(), payload.len()) {
+            (None, _) | (_, 1) => return Err(ParsingError),
+            (Some(first), 3..) if first >> 7 != 0 => return Err(ParsingError),
+            _ => (),
+        }
+        Ok(Self(payload))
+    }
+
+    /// Whether the message assigns a single resource (rather than a list).
+    #[must_use]
+    pub fn single(&self) -> bool {
+        self.0[0] >> 7 != 0
+    }
+
+    /// The 7-bit ID of the group.
+    #[must_use]
+    pub fn group_id(&self) -> u8 {
+        self.0[0] & 0x7f
+    }
+
+    /// The tags of the assigned resources.
+    pub fn resource_tags(&self) -> impl Iterator<Item = ResourceTag> + use<'buf> {
+        self.0[1..].iter().map(|byte| ResourceTag {
+            direct: byte >> 7 != 0,
+            tag: byte & 0x7f,
+        })
+    }
+}
+
+/// Builder for a Group Assignment message.
+///
+/// It holds up to 8 resource tags.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GroupAssignmentBuilder {
+    group_id: u8,
+    tags: [u8; MAX_RESOURCE_TAGS],
+    count: u8,
+}
+
+impl GroupAssignmentBuilder {
+    /// Creates a Group Assignment message for the given group with a first resource tag.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the group ID or tag exceed 7 bits.
+    pub fn new(group_id: u8, tag: ResourceTag) -> Result<Self, ValueRangeError> {
+        if group_id > 0x7f {
+            return Err(ValueRangeError);
+        }
+        Self {
+            group_id,
+            tags: [0; MAX_RESOURCE_TAGS],
+            count: 0,
+        }
+        .resource_tag(tag)
+    }
+
+    /// Adds a further resource tag.
+    ///
+    /// The Single flag is set as long as there is only one tag.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the tag exceeds 7 bits or the builder is full.
+    pub fn resource_tag(mut self, tag: ResourceTag) -> Result<Self, ValueRangeError> {
+        let slot = self
+            .tags
+            .get_mut(usize::from(self.count))
+            .ok_or(ValueRangeError)?;
+        if tag.tag > 0x7f {
+            return Err(ValueRangeError);
+        }
+        *slot = (u8::from(tag.direct) << 7) | tag.tag;
+        self.count += 1;
+        Ok(self)
+    }
+}
+
+impl SerializeIe for GroupAssignmentBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::GROUP_ASSIGNMENT);
+
+    fn payload_len(&self) -> usize {
+        1 + usize::from(self.count)
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[(u8::from(self.count == 1) << 7) | self.group_id])?;
+        w.write_all(&self.tags[..usize::from(self.count)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mac_ie::InformationElement;
+
+    #[test]
+    fn test_broadcast_indication() {
+        // Paging of short ID 0x1234, in an IE without length field
+        let data = [0x16, 0x00, 0x12, 0x34];
+        let mut remaining = &data[..];
+        let ie = InformationElement::parse(&mut remaining).unwrap();
+        assert!(remaining.is_empty());
+        let indication = BroadcastIndication::parse(ie.payload()).unwrap();
+        assert_eq!(indication.indication_type(), IndicationType::Paging);
+        assert_eq!(indication.rd_id(), RdId::Short(0x1234));
+        assert_eq!(indication.feedback(), None);
+        assert!(!indication.resource_allocation());
+        assert!(indication.pages(0x0000_1234, 0x1234));
+        assert!(!indication.pages(0x0000_1234, 0x4321));
+
+        let mut written = [0; 8];
+        BroadcastIndicationBuilder::random_access_response(RdId::Long(0xcafe_0001), true)
+            .feedback(FeedbackType::ChannelQuality, 5)
+            .resource_allocation(true)
+            .serialize(&mut &mut written[..])
+            .unwrap();
+        assert_eq!(written, [0x56, 6, 0x3b, 0xca, 0xfe, 0x00, 0x01, 5]);
+        let indication = BroadcastIndication::parse(&written[2..]).unwrap();
+        assert_eq!(
+            indication.indication_type(),
+            IndicationType::RandomAccessResponse
+        );
+        assert_eq!(indication.rd_id(), RdId::Long(0xcafe_0001));
+        assert!(indication.ack());
+        assert_eq!(
+            indication.feedback(),
+            Some((FeedbackType::ChannelQuality, 5))
+        );
+        assert!(indication.resource_allocation());
+        assert!(!indication.pages(0xcafe_0001, 0));
+
+        // Reserved indication types
+        BroadcastIndication::parse(&[0x40, 0x12, 0x34]).unwrap_err();
+        BroadcastIndication::parse(&[0x60, 0x12, 0x34]).unwrap_err();
+        // Truncated long RD ID
+        BroadcastIndication::parse(&[0x10, 0x12, 0x34]).unwrap_err();
+    }
+
+    #[test]
+    fn test_group_assignment() {
+        let mut written = [0; 5];
+        GroupAssignmentBuilder::new(
+            5,
+            ResourceTag {
+                direct: true,
+                tag: 1,
+            },
+        )
+        .unwrap()
+        .resource_tag(ResourceTag {
+            direct: false,
+            tag: 2,
+        })
+        .unwrap()
+        .serialize(&mut &mut written[..])
+        .unwrap();
+        assert_eq!(written, [0x57, 3, 0x05, 0x81, 0x02]);
+
+        let assignment = GroupAssignment::parse(&written[2..]).unwrap();
+        assert!(!assignment.single());
+        assert_eq!(assignment.group_id(), 5);
+        let mut tags = assignment.resource_tags();
+        assert_eq!(
+            tags.next(),
+            Some(ResourceTag {
+                direct: true,
+                tag: 1
+            })
+        );
+        assert_eq!(tags.next().map(|t| t.tag), Some(2));
+        assert_eq!(tags.next(), None);
+
+        let assignment = GroupAssignment::parse(&[0x85, 0x03]).unwrap();
+        assert!(assignment.single());
+        GroupAssignment::parse(&[0x85, 0x03, 0x04]).unwrap_err();
+        GroupAssignment::parse(&[0x05]).unwrap_err();
+    }
+}
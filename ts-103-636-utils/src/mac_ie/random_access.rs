@@ -0,0 +1,557 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Random Access Resource IE
+//!
+//! This is the payload of the
+//! [`RANDOM_ACCESS_RESOURCE`][numbers::mac_ie::ie6bit::RANDOM_ACCESS_RESOURCE] IE as defined in
+//! Section 6.4.3.4 of ETSI TS 103 636-4 V2.1.1, through which an FT announces when PTs may
+//! contend for access.
+
+use super::beacon::channel;
+use super::{AnyIeType, SerializeIe, flag};
+use crate::pcc::PacketLengthType;
+use crate::timing::{self, FrameReference};
+use crate::{ParsingError, ValueRangeError};
+use ts_103_636_numbers as numbers;
+
+/// Subcarrier scaling factor assumed for the start subslot and subslot lengths.
+///
+/// The Start Subslot field is only 8 bit long for µ up to 4; this module does not support larger
+/// values.
+const MU: u8 = 1;
+
+pub use super::Repeat;
+
+/// Decodes the 2-bit Repeat field, which can not express that additions are allowed.
+fn repeat_from_bits(bits: u8) -> Option<Repeat> {
+    Repeat::from_code(bits)
+        .filter(|repeat| matches!(repeat, Repeat::Single | Repeat::Frames | Repeat::Subslots))
+}
+
+/// Validity value that indicates a permanent allocation.
+pub const VALIDITY_PERMANENT: u8 = 0xff;
+
+/// Length of a Random Access Resource payload as indicated by its leading flags.
+///
+/// Returns `None` if the data is too short to tell.
+pub(super) fn random_access_resource_length(data: &[u8]) -> Option<usize> {
+    let flags = *data.first()?;
+    let repeat = if flags & 0x18 == 0 { 0 } else { 2 };
+    Some(5 + repeat + flag(flags, 2) + 2 * flag(flags, 1) + 2 * flag(flags, 0))
+}
+
+/// A time window in which a transmission may be placed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TxWindow {
+    /// Start time of the window.
+    pub start: u64,
+    /// Duration of the window, in ticks.
+    pub duration: u64,
+}
+
+/// View on a Random Access Resource IE.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RandomAccessResource<'buf>(&'buf [u8]);
+
+impl<'buf> RandomAccessResource<'buf> {
+    /// Interprets the payload of a Random Access Resource IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the one announced by its flags, or if the
+    /// Repeat field has the reserved value.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if random_access_resource_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        repeat_from_bits((payload[0] >> 3) & 0x03).ok_or(ParsingError)?;
+        Ok(Self(payload))
+    }
+
+    fn flags(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// The Repeat field.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "Reserved value was excluded at parsing"
+    )]
+    #[must_use]
+    pub fn repeat(&self) -> Repeat {
+        repeat_from_bits((self.flags() >> 3) & 0x03).expect("Checked at parsing")
+    }
+
+    /// The first subslot of the resource in its frame.
+    #[must_use]
+    pub fn start_subslot(&self) -> u8 {
+        self.0[1]
+    }
+
+    /// The unit of [`Self::length()`].
+    #[must_use]
+    pub fn length_type(&self) -> PacketLengthType {
+        PacketLengthType::from_bit(self.0[2] >> 7)
+    }
+
+    /// The 7-bit length of the resource in units of [`Self::length_type()`].
+    #[must_use]
+    pub fn length(&self) -> u8 {
+        self.0[2] & 0x7f
+    }
+
+    /// The unit of [`Self::max_length()`].
+    #[must_use]
+    pub fn max_length_type(&self) -> PacketLengthType {
+        PacketLengthType::from_bit(self.0[3] >> 7)
+    }
+
+    /// The 4-bit maximum length of a single random access transmission, in units of
+    /// [`Self::max_length_type()`].
+    #[must_use]
+    pub fn max_length(&self) -> u8 {
+        (self.0[3] >> 3) & 0x0f
+    }
+
+    /// The 3-bit CW Min Sig field, from which the minimal contention window is derived.
+    #[must_use]
+    pub fn cw_min_sig(&self) -> u8 {
+        self.0[3] & 0x07
+    }
+
+    /// The DECT Delay flag, which selects when the response window starts.
+    #[must_use]
+    pub fn dect_delay(&self) -> bool {
+        self.0[4] >> 7 != 0
+    }
+
+    /// The 4-bit Response Window field (in its coded form, being the window length in subslots
+    /// minus one).
+    #[must_use]
+    pub fn response_window(&self) -> u8 {
+        (self.0[4] >> 3) & 0x0f
+    }
+
+    /// The 3-bit CW Max Sig field, from which the maximal contention window is derived.
+    #[must_use]
+    pub fn cw_max_sig(&self) -> u8 {
+        self.0[4] & 0x07
+    }
+
+    /// The interval between repetitions, in frames or subslots depending on [`Self::repeat()`].
+    #[must_use]
+    pub fn repetition(&self) -> Option<u8> {
+        (self.repeat() != Repeat::Single).then(|| self.0[5])
+    }
+
+    /// The number of frames for which the resource is valid, or [`VALIDITY_PERMANENT`].
+    #[must_use]
+    pub fn validity(&self) -> Option<u8> {
+        (self.repeat() != Repeat::Single).then(|| self.0[6])
+    }
+
+    fn sfn_offset(&self) -> usize {
+        if self.repeat() == Repeat::Single {
+            5
+        } else {
+            7
+        }
+    }
+
+    /// The System Frame Number in which the resource starts, if given.
+    ///
+    /// If absent, the resource starts in the frame in which the IE was received.
+    #[must_use]
+    pub fn system_frame_number(&self) -> Option<u8> {
+        (flag(self.flags(), 2) != 0).then(|| self.0[self.sfn_offset()])
+    }
+
+    /// The channel of the resource, if it is not the channel on which the IE was received.
+    #[must_use]
+    pub fn channel(&self) -> Option<u16> {
+        let offset = self.sfn_offset() + flag(self.flags(), 2);
+        (flag(self.flags(), 1) != 0).then(|| channel(&self.0[offset..]))
+    }
+
+    /// The channel on which the response is sent, if it is not [`Self::channel()`].
+    #[must_use]
+    pub fn channel_2(&self) -> Option<u16> {
+        let offset = self.sfn_offset() + flag(self.flags(), 2) + 2 * flag(self.flags(), 1);
+        (flag(self.flags(), 0) != 0).then(|| channel(&self.0[offset..]))
+    }
+
+    /// Lists the windows in which a PT may start a random access transmission.
+    ///
+    /// The `frame` is the frame relative to which the IE is interpreted (the frame of the beacon
+    /// that carried it). Only windows that start at or after `now` are produced. For permanent
+    /// resources, the iterator does not terminate.
+    ///
+    /// This assumes a subcarrier scaling factor µ of 1.
+    pub fn tx_windows(
+        &self,
+        frame: FrameReference,
+        now: u64,
+    ) -> impl Iterator<Item = TxWindow> + use<> {
+        let first_frame = match self.system_frame_number() {
+            Some(sfn) => frame.start_of_frame_number(sfn),
+            None => frame.start,
+        };
+        let offset = u64::from(self.start_subslot()) * timing::subslot(MU);
+        let duration = u64::from(self.length())
+            * match self.length_type() {
+                PacketLengthType::Subslots => timing::subslot(MU),
+                PacketLengthType::Slots => timing::SLOT,
+            };
+
+        let step = match (self.repeat(), self.repetition()) {
+            (Repeat::Frames, Some(repetition @ 1..)) => Some(u64::from(repetition) * timing::FRAME),
+            (Repeat::Subslots, Some(repetition @ 1..)) => {
+                Some(u64::from(repetition) * timing::subslot(MU))
+            }
+            // Single, or a repetition of 0 that would place all repetitions in the same spot
+            _ => None,
+        };
+        let validity = self
+            .validity()
+            .filter(|validity| *validity != VALIDITY_PERMANENT);
+
+        timing::Repetitions::new(first_frame, step, validity)
+            .starts(offset)
+            .map(move |start| TxWindow { start, duration })
+            .skip_while(move |window| window.start < now)
+    }
+}
+
+/// Builder for a Random Access Resource IE.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RandomAccessResourceBuilder {
+    repeat: Repeat,
+    repetition: u8,
+    validity: u8,
+    system_frame_number: Option<u8>,
+    channel: Option<u16>,
+    channel_2: Option<u16>,
+    start_subslot: u8,
+    length_type: PacketLengthType,
+    length: u8,
+    max_length_type: PacketLengthType,
+    max_length: u8,
+    cw_min_sig: u8,
+    dect_delay: bool,
+    response_window: u8,
+    cw_max_sig: u8,
+}
+
+impl RandomAccessResourceBuilder {
+    /// Creates a single-use resource at the given position in the frame.
+    ///
+    /// The maximum transmission length starts out as the full resource length (or the largest
+    /// expressible value), and all contention and response parameters as 0.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the length exceeds 7 bits.
+    pub fn new(
+        start_subslot: u8,
+        length_type: PacketLengthType,
+        length: u8,
+    ) -> Result<Self, ValueRangeError> {
+        if length > 0x7f {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            repeat: Repeat::Single,
+            repetition: 0,
+            validity: 0,
+            system_frame_number: None,
+            channel: None,
+            channel_2: None,
+            start_subslot,
+            length_type,
+            length,
+            max_length_type: length_type,
+            max_length: length.min(0x0f),
+            cw_min_sig: 0,
+            dect_delay: false,
+            response_window: 0,
+            cw_max_sig: 0,
+        })
+    }
+
+    /// Sets the repetition of the resource.
+    ///
+    /// For [`Repeat::Single`], the `repetition` and `validity` are ignored.
+    ///
+    /// # Errors
+    ///
+    /// This errs for the Repeat values that allow additions, which this IE can not express.
+    pub fn repeat(
+        mut self,
+        repeat: Repeat,
+        repetition: u8,
+        validity: u8,
+    ) -> Result<Self, ValueRangeError> {
+        if !matches!(repeat, Repeat::Single | Repeat::Frames | Repeat::Subslots) {
+            return Err(ValueRangeError);
+        }
+        self.repeat = repeat;
+        self.repetition = repetition;
+        self.validity = validity;
+        Ok(self)
+    }
+
+    /// Sets the System Frame Number in which the resource starts.
+    #[must_use]
+    pub fn system_frame_number(mut self, system_frame_number: u8) -> Self {
+        self.system_frame_number = Some(system_frame_number);
+        self
+    }
+
+    /// Sets the channel of the resource.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the channel exceeds 13 bits.
+    pub fn channel(mut self, channel: u16) -> Result<Self, ValueRangeError> {
+        if channel > 0x1fff {
+            return Err(ValueRangeError);
+        }
+        self.channel = Some(channel);
+        Ok(self)
+    }
+
+    /// Sets the channel on which the response is sent.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the channel exceeds 13 bits.
+    pub fn channel_2(mut self, channel: u16) -> Result<Self, ValueRangeError> {
+        if channel > 0x1fff {
+            return Err(ValueRangeError);
+        }
+        self.channel_2 = Some(channel);
+        Ok(self)
+    }
+
+    /// Sets the maximum length of a single random access transmission.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the length exceeds 4 bits.
+    pub fn max_length(
+        mut self,
+        max_length_type: PacketLengthType,
+        max_length: u8,
+    ) -> Result<Self, ValueRangeError> {
+        if max_length > 0x0f {
+            return Err(ValueRangeError);
+        }
+        self.max_length_type = max_length_type;
+        self.max_length = max_length;
+        Ok(self)
+    }
+
+    /// Sets the CW Min Sig and CW Max Sig fields.
+    ///
+    /// # Errors
+    ///
+    /// This errs if either value exceeds 3 bits.
+    pub fn contention_window(
+        mut self,
+        cw_min_sig: u8,
+        cw_max_sig: u8,
+    ) -> Result<Self, ValueRangeError> {
+        if cw_min_sig > 0x07 || cw_max_sig > 0x07 {
+            return Err(ValueRangeError);
+        }
+        self.cw_min_sig = cw_min_sig;
+        self.cw_max_sig = cw_max_sig;
+        Ok(self)
+    }
+
+    /// Sets the coded Response Window and the DECT Delay flag.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the response window exceeds 4 bits.
+    pub fn response_window(
+        mut self,
+        response_window: u8,
+        dect_delay: bool,
+    ) -> Result<Self, ValueRangeError> {
+        if response_window > 0x0f {
+            return Err(ValueRangeError);
+        }
+        self.response_window = response_window;
+        self.dect_delay = dect_delay;
+        Ok(self)
+    }
+}
+
+impl SerializeIe for RandomAccessResourceBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::RANDOM_ACCESS_RESOURCE);
+
+    fn payload_len(&self) -> usize {
+        5 + if self.repeat == Repeat::Single { 0 } else { 2 }
+            + usize::from(self.system_frame_number.is_some())
+            + 2 * usize::from(self.channel.is_some())
+            + 2 * usize::from(self.channel_2.is_some())
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        let type_bit = |type_: PacketLengthType| type_.bit() << 7;
+        w.write_all(&[
+            (self.repeat.code() << 3)
+                | (u8::from(self.system_frame_number.is_some()) << 2)
+                | (u8::from(self.channel.is_some()) << 1)
+                | u8::from(self.channel_2.is_some()),
+            self.start_subslot,
+            type_bit(self.length_type) | self.length,
+            type_bit(self.max_length_type) | (self.max_length << 3) | self.cw_min_sig,
+            (u8::from(self.dect_delay) << 7) | (self.response_window << 3) | self.cw_max_sig,
+        ])?;
+        if self.repeat != Repeat::Single {
+            w.write_all(&[self.repetition, self.validity])?;
+        }
+        if let Some(system_frame_number) = self.system_frame_number {
+            w.write_all(&[system_frame_number])?;
+        }
+        if let Some(channel) = self.channel {
+            w.write_all(&channel.to_be_bytes())?;
+        }
+        if let Some(channel) = self.channel_2 {
+            w.write_all(&channel.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_build() {
+        // From the beacon used across tests, as sent by the dect_shell
+        let payload = [8, 12, 138, 160, 215, 2, 100];
+        let rach = RandomAccessResource::parse(&payload).unwrap();
+        assert_eq!(rach.repeat(), Repeat::Frames);
+        assert_eq!(rach.start_subslot(), 12);
+        assert_eq!(rach.length_type(), PacketLengthType::Slots);
+        assert_eq!(rach.length(), 10);
+        assert_eq!(rach.max_length_type(), PacketLengthType::Slots);
+        assert_eq!(rach.max_length(), 4);
+        assert_eq!(rach.cw_min_sig(), 0);
+        assert!(rach.dect_delay());
+        assert_eq!(rach.response_window(), 10);
+        assert_eq!(rach.cw_max_sig(), 7);
+        assert_eq!(rach.repetition(), Some(2));
+        assert_eq!(rach.validity(), Some(100));
+        assert_eq!(rach.system_frame_number(), None);
+        assert_eq!(rach.channel(), None);
+        assert_eq!(rach.channel_2(), None);
+
+        let builder = RandomAccessResourceBuilder::new(12, PacketLengthType::Slots, 10)
+            .unwrap()
+            .repeat(Repeat::Frames, 2, 100)
+            .unwrap()
+            .max_length(PacketLengthType::Slots, 4)
+            .unwrap()
+            .contention_window(0, 7)
+            .unwrap()
+            .response_window(10, true)
+            .unwrap();
+        let mut written = [0; 9];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(written, [83, 7, 8, 12, 138, 160, 215, 2, 100]);
+        assert!(
+            RandomAccessResourceBuilder::new(12, PacketLengthType::Slots, 10)
+                .unwrap()
+                .repeat(Repeat::FramesAdditionsAllowed, 2, 100)
+                .is_err()
+        );
+
+        let builder = RandomAccessResourceBuilder::new(3, PacketLengthType::Subslots, 2)
+            .unwrap()
+            .system_frame_number(9)
+            .channel(1667)
+            .unwrap()
+            .channel_2(1669)
+            .unwrap();
+        let mut written = [0; 10];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        assert_eq!(builder.payload_len(), 10);
+        let rach = RandomAccessResource::parse(&written).unwrap();
+        assert_eq!(rach.repeat(), Repeat::Single);
+        assert_eq!(rach.repetition(), None);
+        assert_eq!(rach.system_frame_number(), Some(9));
+        assert_eq!(rach.channel(), Some(1667));
+        assert_eq!(rach.channel_2(), Some(1669));
+        assert_eq!(rach.max_length(), 2);
+
+        // Reserved repeat value
+        RandomAccessResource::parse(&[0x18, 12, 138, 160, 215, 2, 100]).unwrap_err();
+        RandomAccessResource::parse(&payload[..6]).unwrap_err();
+    }
+
+    #[test]
+    fn test_tx_windows() {
+        let payload = [8, 12, 138, 160, 215, 2, 100];
+        let rach = RandomAccessResource::parse(&payload).unwrap();
+        let frame = FrameReference {
+            start: 1_000_000,
+            system_frame_number: 176,
+        };
+
+        let mut windows = rach.tx_windows(frame, frame.start);
+        assert_eq!(
+            windows.next(),
+            Some(TxWindow {
+                start: 1_000_000 + 12 * 14_400,
+                duration: 10 * 28_800,
+            })
+        );
+        assert_eq!(
+            windows.next(),
+            Some(TxWindow {
+                start: 1_000_000 + 2 * 691_200 + 12 * 14_400,
+                duration: 10 * 28_800,
+            })
+        );
+        // Every other frame during 100 frames
+        assert_eq!(windows.count(), 48);
+
+        // Past windows are skipped
+        let now = frame.start + 50 * 691_200;
+        let mut windows = rach.tx_windows(frame, now);
+        assert_eq!(
+            windows.next().unwrap().start,
+            1_000_000 + 50 * 691_200 + 12 * 14_400
+        );
+        assert_eq!(windows.count(), 24);
+
+        // Single resource in a later frame, counting across an SFN wrap-around
+        let builder = RandomAccessResourceBuilder::new(4, PacketLengthType::Subslots, 2)
+            .unwrap()
+            .system_frame_number(2);
+        let mut written = [0; 6];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        let rach = RandomAccessResource::parse(&written).unwrap();
+        let frame = FrameReference {
+            start: 1_000_000,
+            system_frame_number: 254,
+        };
+        let mut windows = rach.tx_windows(frame, 0);
+        assert_eq!(
+            windows.next(),
+            Some(TxWindow {
+                start: 1_000_000 + 4 * 691_200 + 4 * 14_400,
+                duration: 2 * 14_400,
+            })
+        );
+        assert_eq!(windows.next(), None);
+    }
+}
@@ -0,0 +1,689 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Resource Allocation IE
+//!
+//! This is the payload of the
+//! [`RESOURCE_ALLOCATION`][numbers::mac_ie::ie6bit::RESOURCE_ALLOCATION] IE as defined in Section
+//! 6.4.3.3 of ETSI TS 103 636-4 V2.1.1, through which an FT schedules resources for downlink and
+//! uplink transmissions with a PT.
+
+use super::beacon::channel;
+use super::random_access::VALIDITY_PERMANENT;
+use super::{AnyIeType, SerializeIe, flag};
+use crate::pcc::PacketLengthType;
+use crate::timing::{self, FrameReference};
+use crate::{ParsingError, ValueRangeError};
+use ts_103_636_numbers as numbers;
+
+/// Subcarrier scaling factor assumed for the start subslots and subslot lengths.
+///
+/// The start subslot fields are only 8 bit long for µ up to 4; this module does not support
+/// larger values.
+const MU: u8 = 1;
+
+coded_enum! {
+    /// Allocation Type: which directions are allocated.
+    pub enum AllocationType {
+        /// All scheduled resources are released; no allocation follows.
+        ReleaseAll = 0,
+        /// Downlink resources are allocated.
+        Downlink = 1,
+        /// Uplink resources are allocated.
+        Uplink = 2,
+        /// Both downlink and uplink resources are allocated.
+        DownlinkAndUplink = 3,
+    }
+}
+
+pub use super::Repeat;
+
+/// Direction of a scheduled transmission.
+///
+/// Downlink transmissions are sent by the FT and received by the PT, uplink transmissions are
+/// sent by the PT and received by the FT.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    /// From FT to PT.
+    Downlink,
+    /// From PT to FT.
+    Uplink,
+}
+
+/// Position and length of a resource within its frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Allocation {
+    /// The first subslot of the resource.
+    pub start_subslot: u8,
+    /// The unit of [`Self::length`].
+    pub length_type: PacketLengthType,
+    /// The 7-bit length of the resource.
+    pub length: u8,
+}
+
+impl Allocation {
+    fn parse(data: &[u8]) -> Self {
+        Self {
+            start_subslot: data[0],
+            length_type: PacketLengthType::from_bit(data[1] >> 7),
+            length: data[1] & 0x7f,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; 2] {
+        [
+            self.start_subslot,
+            (self.length_type.bit() << 7) | self.length,
+        ]
+    }
+
+    fn offset(self) -> u64 {
+        u64::from(self.start_subslot) * timing::subslot(MU)
+    }
+
+    fn duration(self) -> u64 {
+        u64::from(self.length)
+            * match self.length_type {
+                PacketLengthType::Subslots => timing::subslot(MU),
+                PacketLengthType::Slots => timing::SLOT,
+            }
+    }
+}
+
+/// A concrete occurrence of an allocated resource.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Occurrence {
+    /// Start time of the occurrence.
+    pub start: u64,
+    /// Duration of the occurrence, in ticks.
+    pub duration: u64,
+    /// Direction in which data is sent.
+    pub direction: Direction,
+}
+
+/// Length of the allocations as indicated by the leading flags.
+fn allocations_len(flags: u8) -> usize {
+    match flags >> 6 {
+        0 => 0,
+        3 => 4,
+        _ => 2,
+    }
+}
+
+/// Length of a Resource Allocation payload as indicated by its leading flags.
+///
+/// Returns `None` if the data is too short to tell.
+fn resource_allocation_length(data: &[u8]) -> Option<usize> {
+    let flags = *data.first()?;
+    let flags2 = *data.get(1)?;
+    let repeat = if flags & 0x0e == 0 { 0 } else { 2 };
+    Some(
+        2 + allocations_len(flags)
+            + 2 * flag(flags, 4)
+            + repeat
+            + flag(flags, 0)
+            + 2 * flag(flags2, 7)
+            + flag(flags2, 6),
+    )
+}
+
+/// View on a Resource Allocation IE.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResourceAllocation<'buf>(&'buf [u8]);
+
+impl<'buf> ResourceAllocation<'buf> {
+    /// Interprets the payload of a Resource Allocation IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload length does not match the one announced by its flags, or if the
+    /// Repeat field has a reserved value.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        if resource_allocation_length(payload) != Some(payload.len()) {
+            return Err(ParsingError);
+        }
+        Repeat::from_code((payload[0] >> 1) & 0x07).ok_or(ParsingError)?;
+        Ok(Self(payload))
+    }
+
+    fn flags(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// The directions for which resources are allocated.
+    #[expect(clippy::missing_panics_doc, reason = "All 2-bit values are defined")]
+    #[must_use]
+    pub fn allocation_type(&self) -> AllocationType {
+        AllocationType::from_code(self.flags() >> 6).expect("All values are defined")
+    }
+
+    /// The Add flag: Whether the allocation is added to previous allocations (rather than
+    /// replacing them).
+    #[must_use]
+    pub fn additive(&self) -> bool {
+        flag(self.flags(), 5) != 0
+    }
+
+    /// The Repeat field.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "Reserved values were excluded at parsing"
+    )]
+    #[must_use]
+    pub fn repeat(&self) -> Repeat {
+        Repeat::from_code((self.flags() >> 1) & 0x07).expect("Checked at parsing")
+    }
+
+    /// The resource allocated for downlink transmissions, if any.
+    #[must_use]
+    pub fn downlink(&self) -> Option<Allocation> {
+        matches!(
+            self.allocation_type(),
+            AllocationType::Downlink | AllocationType::DownlinkAndUplink
+        )
+        .then(|| Allocation::parse(&self.0[2..4]))
+    }
+
+    /// The resource allocated for uplink transmissions, if any.
+    #[must_use]
+    pub fn uplink(&self) -> Option<Allocation> {
+        match self.allocation_type() {
+            AllocationType::Uplink => Some(Allocation::parse(&self.0[2..4])),
+            AllocationType::DownlinkAndUplink => Some(Allocation::parse(&self.0[4..6])),
+            _ => None,
+        }
+    }
+
+    fn short_rd_id_offset(&self) -> usize {
+        2 + allocations_len(self.flags())
+    }
+
+    /// The short RD ID of the device the resources are allocated to, if given.
+    #[must_use]
+    pub fn short_rd_id(&self) -> Option<u16> {
+        let offset = self.short_rd_id_offset();
+        (flag(self.flags(), 4) != 0)
+            .then(|| u16::from_be_bytes([self.0[offset], self.0[offset + 1]]))
+    }
+
+    fn repetition_offset(&self) -> usize {
+        self.short_rd_id_offset() + 2 * flag(self.flags(), 4)
+    }
+
+    /// The interval between repetitions, in frames or subslots depending on [`Self::repeat()`].
+    #[must_use]
+    pub fn repetition(&self) -> Option<u8> {
+        (self.repeat() != Repeat::Single).then(|| self.0[self.repetition_offset()])
+    }
+
+    /// The number of frames for which the allocation is valid, or [`VALIDITY_PERMANENT`].
+    #[must_use]
+    pub fn validity(&self) -> Option<u8> {
+        (self.repeat() != Repeat::Single).then(|| self.0[self.repetition_offset() + 1])
+    }
+
+    fn sfn_offset(&self) -> usize {
+        self.repetition_offset()
+            + if self.repeat() == Repeat::Single {
+                0
+            } else {
+                2
+            }
+    }
+
+    /// The System Frame Number in which the allocation starts, if given.
+    ///
+    /// If absent, the allocation starts in the frame in which the IE was received.
+    #[must_use]
+    pub fn system_frame_number(&self) -> Option<u8> {
+        (flag(self.flags(), 0) != 0).then(|| self.0[self.sfn_offset()])
+    }
+
+    /// The channel of the allocation, if it is not the channel on which the IE was received.
+    #[must_use]
+    pub fn channel(&self) -> Option<u16> {
+        let offset = self.sfn_offset() + flag(self.flags(), 0);
+        (flag(self.0[1], 7) != 0).then(|| channel(&self.0[offset..]))
+    }
+
+    /// The 4-bit coded dectScheduledResourceFailure timer, if given.
+    #[must_use]
+    pub fn scheduled_resource_failure(&self) -> Option<u8> {
+        let offset = self.sfn_offset() + flag(self.flags(), 0) + 2 * flag(self.0[1], 7);
+        (flag(self.0[1], 6) != 0).then(|| self.0[offset] & 0x0f)
+    }
+
+    /// Expands the allocation into its concrete occurrences.
+    ///
+    /// The `frame` is the frame relative to which the IE is interpreted (the frame in which it was
+    /// received). Only occurrences that start at or after `now` are produced, in the order of
+    /// their start times. For permanent allocations, the iterator does not terminate.
+    ///
+    /// This assumes a subcarrier scaling factor µ of 1.
+    pub fn occurrences(
+        &self,
+        frame: FrameReference,
+        now: u64,
+    ) -> impl Iterator<Item = Occurrence> + use<> {
+        let first_frame = match self.system_frame_number() {
+            Some(sfn) => frame.start_of_frame_number(sfn),
+            None => frame.start,
+        };
+        let step = match (self.repeat(), self.repetition()) {
+            (Repeat::Frames | Repeat::FramesAdditionsAllowed, Some(repetition @ 1..)) => {
+                Some(u64::from(repetition) * timing::FRAME)
+            }
+            (Repeat::Subslots | Repeat::SubslotsAdditionsAllowed, Some(repetition @ 1..)) => {
+                Some(u64::from(repetition) * timing::subslot(MU))
+            }
+            // Single, or a repetition of 0 that would place all repetitions in the same spot
+            _ => None,
+        };
+        let validity = self
+            .validity()
+            .filter(|validity| *validity != VALIDITY_PERMANENT);
+        let repetitions = timing::Repetitions::new(first_frame, step, validity);
+
+        // Each direction's occurrences are in order; they are merged by start time, as a short
+        // repetition step may interleave them.
+        let mut streams = [
+            self.downlink().map(|a| (a, Direction::Downlink)),
+            self.uplink().map(|a| (a, Direction::Uplink)),
+        ]
+        .map(|allocation| {
+            allocation.map(|(allocation, direction)| {
+                repetitions
+                    .starts(allocation.offset())
+                    .map(move |start| Occurrence {
+                        start,
+                        duration: allocation.duration(),
+                        direction,
+                    })
+                    .peekable()
+            })
+        });
+
+        core::iter::from_fn(move || {
+            let [downlink, uplink] = &mut streams;
+            let downlink_start = downlink.as_mut().and_then(|s| s.peek()).map(|o| o.start);
+            let uplink_start = uplink.as_mut().and_then(|s| s.peek()).map(|o| o.start);
+            match (downlink_start, uplink_start) {
+                (Some(downlink_start), Some(uplink_start)) if uplink_start < downlink_start => {
+                    uplink.as_mut()?.next()
+                }
+                (Some(_), _) => downlink.as_mut()?.next(),
+                (None, _) => uplink.as_mut()?.next(),
+            }
+        })
+        .skip_while(move |occurrence| occurrence.start < now)
+    }
+}
+
+/// Builder for a Resource Allocation IE.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResourceAllocationBuilder {
+    allocation_type: AllocationType,
+    allocations: [Option<Allocation>; 2],
+    additive: bool,
+    short_rd_id: Option<u16>,
+    repeat: Repeat,
+    repetition: u8,
+    validity: u8,
+    system_frame_number: Option<u8>,
+    channel: Option<u16>,
+    scheduled_resource_failure: Option<u8>,
+}
+
+impl ResourceAllocationBuilder {
+    /// Creates a single allocation of the given downlink and uplink resources.
+    ///
+    /// # Errors
+    ///
+    /// This errs if neither resource is given (see [`Self::release_all()`] for that), or if a
+    /// length exceeds 7 bits.
+    pub fn new(
+        downlink: Option<Allocation>,
+        uplink: Option<Allocation>,
+    ) -> Result<Self, ValueRangeError> {
+        let allocation_type = match (downlink, uplink) {
+            (None, None) => return Err(ValueRangeError),
+            (Some(_), None) => AllocationType::Downlink,
+            (None, Some(_)) => AllocationType::Uplink,
+            (Some(_), Some(_)) => AllocationType::DownlinkAndUplink,
+        };
+        if downlink.into_iter().chain(uplink).any(|a| a.length > 0x7f) {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            allocation_type,
+            allocations: [downlink, uplink],
+            ..Self::release_all()
+        })
+    }
+
+    /// Creates a message that releases all scheduled resources.
+    #[must_use]
+    pub fn release_all() -> Self {
+        Self {
+            allocation_type: AllocationType::ReleaseAll,
+            allocations: [None, None],
+            additive: false,
+            short_rd_id: None,
+            repeat: Repeat::Single,
+            repetition: 0,
+            validity: 0,
+            system_frame_number: None,
+            channel: None,
+            scheduled_resource_failure: None,
+        }
+    }
+
+    /// Sets the Add flag.
+    #[must_use]
+    pub fn additive(mut self, additive: bool) -> Self {
+        self.additive = additive;
+        self
+    }
+
+    /// Sets the short RD ID of the device the resources are allocated to.
+    #[must_use]
+    pub fn short_rd_id(mut self, short_rd_id: u16) -> Self {
+        self.short_rd_id = Some(short_rd_id);
+        self
+    }
+
+    /// Sets the repetition of the allocation.
+    ///
+    /// For [`Repeat::Single`], the `repetition` and `validity` are ignored.
+    #[must_use]
+    pub fn repeat(mut self, repeat: Repeat, repetition: u8, validity: u8) -> Self {
+        self.repeat = repeat;
+        self.repetition = repetition;
+        self.validity = validity;
+        self
+    }
+
+    /// Sets the System Frame Number in which the allocation starts.
+    #[must_use]
+    pub fn system_frame_number(mut self, system_frame_number: u8) -> Self {
+        self.system_frame_number = Some(system_frame_number);
+        self
+    }
+
+    /// Sets the channel of the allocation.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the channel exceeds 13 bits.
+    pub fn channel(mut self, channel: u16) -> Result<Self, ValueRangeError> {
+        if channel > 0x1fff {
+            return Err(ValueRangeError);
+        }
+        self.channel = Some(channel);
+        Ok(self)
+    }
+
+    /// Sets the coded dectScheduledResourceFailure timer.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the value exceeds 4 bits.
+    pub fn scheduled_resource_failure(mut self, code: u8) -> Result<Self, ValueRangeError> {
+        if code > 0x0f {
+            return Err(ValueRangeError);
+        }
+        self.scheduled_resource_failure = Some(code);
+        Ok(self)
+    }
+}
+
+impl SerializeIe for ResourceAllocationBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::RESOURCE_ALLOCATION);
+
+    fn payload_len(&self) -> usize {
+        2 + 2 * self.allocations.iter().flatten().count()
+            + 2 * usize::from(self.short_rd_id.is_some())
+            + if self.repeat == Repeat::Single { 0 } else { 2 }
+            + usize::from(self.system_frame_number.is_some())
+            + 2 * usize::from(self.channel.is_some())
+            + usize::from(self.scheduled_resource_failure.is_some())
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[
+            (self.allocation_type.code() << 6)
+                | (u8::from(self.additive) << 5)
+                | (u8::from(self.short_rd_id.is_some()) << 4)
+                | (self.repeat.code() << 1)
+                | u8::from(self.system_frame_number.is_some()),
+            (u8::from(self.channel.is_some()) << 7)
+                | (u8::from(self.scheduled_resource_failure.is_some()) << 6),
+        ])?;
+        for allocation in self.allocations.iter().flatten() {
+            w.write_all(&allocation.to_bytes())?;
+        }
+        if let Some(short_rd_id) = self.short_rd_id {
+            w.write_all(&short_rd_id.to_be_bytes())?;
+        }
+        if self.repeat != Repeat::Single {
+            w.write_all(&[self.repetition, self.validity])?;
+        }
+        if let Some(system_frame_number) = self.system_frame_number {
+            w.write_all(&[system_frame_number])?;
+        }
+        if let Some(channel) = self.channel {
+            w.write_all(&channel.to_be_bytes())?;
+        }
+        if let Some(code) = self.scheduled_resource_failure {
+            w.write_all(&[code])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_build() {
+        let downlink = Allocation {
+            start_subslot: 20,
+            length_type: PacketLengthType::Subslots,
+            length: 2,
+        };
+        let uplink = Allocation {
+            start_subslot: 4,
+            length_type: PacketLengthType::Slots,
+            length: 1,
+        };
+        let builder = ResourceAllocationBuilder::new(Some(downlink), Some(uplink))
+            .unwrap()
+            .short_rd_id(0x1234)
+            .repeat(Repeat::Frames, 10, 50);
+        let mut written = [0; 12];
+        builder.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(
+            written,
+            [
+                0x40 | 0x12,
+                10,
+                0xd2,
+                0x00,
+                20,
+                0x02,
+                4,
+                0x81,
+                0x12,
+                0x34,
+                10,
+                50
+            ]
+        );
+
+        let allocation = ResourceAllocation::parse(&written[2..]).unwrap();
+        assert_eq!(
+            allocation.allocation_type(),
+            AllocationType::DownlinkAndUplink
+        );
+        assert!(!allocation.additive());
+        assert_eq!(allocation.downlink(), Some(downlink));
+        assert_eq!(allocation.uplink(), Some(uplink));
+        assert_eq!(allocation.short_rd_id(), Some(0x1234));
+        assert_eq!(allocation.repeat(), Repeat::Frames);
+        assert_eq!(allocation.repetition(), Some(10));
+        assert_eq!(allocation.validity(), Some(50));
+        assert_eq!(allocation.system_frame_number(), None);
+        assert_eq!(allocation.channel(), None);
+        assert_eq!(allocation.scheduled_resource_failure(), None);
+
+        let builder = ResourceAllocationBuilder::new(None, Some(uplink))
+            .unwrap()
+            .additive(true)
+            .system_frame_number(7)
+            .channel(1667)
+            .unwrap()
+            .scheduled_resource_failure(3)
+            .unwrap();
+        let mut written = [0; 8];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        assert_eq!(builder.payload_len(), 8);
+        let allocation = ResourceAllocation::parse(&written).unwrap();
+        assert_eq!(allocation.allocation_type(), AllocationType::Uplink);
+        assert!(allocation.additive());
+        assert_eq!(allocation.downlink(), None);
+        assert_eq!(allocation.uplink(), Some(uplink));
+        assert_eq!(allocation.short_rd_id(), None);
+        assert_eq!(allocation.repetition(), None);
+        assert_eq!(allocation.system_frame_number(), Some(7));
+        assert_eq!(allocation.channel(), Some(1667));
+        assert_eq!(allocation.scheduled_resource_failure(), Some(3));
+
+        let mut written = [0; 2];
+        ResourceAllocationBuilder::release_all()
+            .serialize_payload(&mut &mut written[..])
+            .unwrap();
+        let allocation = ResourceAllocation::parse(&written).unwrap();
+        assert_eq!(allocation.allocation_type(), AllocationType::ReleaseAll);
+        assert_eq!(
+            allocation
+                .occurrences(
+                    FrameReference {
+                        start: 0,
+                        system_frame_number: 0
+                    },
+                    0
+                )
+                .count(),
+            0
+        );
+
+        ResourceAllocationBuilder::new(None, None).unwrap_err();
+        // Reserved repeat value
+        ResourceAllocation::parse(&[0x0e, 0, 0, 0]).unwrap_err();
+        ResourceAllocation::parse(&[0x40, 0, 0]).unwrap_err();
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let downlink = Allocation {
+            start_subslot: 20,
+            length_type: PacketLengthType::Subslots,
+            length: 2,
+        };
+        let uplink = Allocation {
+            start_subslot: 4,
+            length_type: PacketLengthType::Slots,
+            length: 1,
+        };
+        let builder = ResourceAllocationBuilder::new(Some(downlink), Some(uplink))
+            .unwrap()
+            .repeat(Repeat::Frames, 10, 50);
+        let mut written = [0; 8];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        let allocation = ResourceAllocation::parse(&written).unwrap();
+        let frame = FrameReference {
+            start: 1_000_000,
+            system_frame_number: 3,
+        };
+
+        let mut occurrences = allocation.occurrences(frame, 1_100_000);
+        // The uplink in the first frame has already started
+        assert_eq!(
+            occurrences.next(),
+            Some(Occurrence {
+                start: 1_000_000 + 20 * 14_400,
+                duration: 2 * 14_400,
+                direction: Direction::Downlink,
+            })
+        );
+        assert_eq!(
+            occurrences.next(),
+            Some(Occurrence {
+                start: 1_000_000 + 10 * 691_200 + 4 * 14_400,
+                duration: 28_800,
+                direction: Direction::Uplink,
+            })
+        );
+        // Remaining frames 10, 20, 30 and 40 in the 50 frames of validity
+        assert_eq!(occurrences.count(), 7);
+
+        // Permanent subslot repetition continues indefinitely
+        let builder = ResourceAllocationBuilder::new(Some(downlink), None)
+            .unwrap()
+            .repeat(Repeat::SubslotsAdditionsAllowed, 4, VALIDITY_PERMANENT);
+        let mut written = [0; 6];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        let allocation = ResourceAllocation::parse(&written).unwrap();
+        let start = allocation
+            .occurrences(frame, 0)
+            .map(|occurrence| occurrence.start)
+            .nth(1000);
+        assert_eq!(start, Some(1_000_000 + 20 * 14_400 + 1000 * 4 * 14_400));
+    }
+
+    #[test]
+    fn test_occurrences_interleaved() {
+        // Repeating more often than the gap between the downlink and the uplink
+        let downlink = Allocation {
+            start_subslot: 20,
+            length_type: PacketLengthType::Subslots,
+            length: 1,
+        };
+        let uplink = Allocation {
+            start_subslot: 4,
+            length_type: PacketLengthType::Subslots,
+            length: 1,
+        };
+        let builder = ResourceAllocationBuilder::new(Some(downlink), Some(uplink))
+            .unwrap()
+            .repeat(Repeat::Subslots, 4, 1);
+        let mut written = [0; 8];
+        builder.serialize_payload(&mut &mut written[..]).unwrap();
+        let allocation = ResourceAllocation::parse(&written).unwrap();
+        let frame = FrameReference {
+            start: 0,
+            system_frame_number: 0,
+        };
+
+        // A frame has 48 subslots at µ = 1; starting after subslot 10
+        let mut occurrences = allocation.occurrences(frame, 10 * 14_400);
+        for subslot in (12..48).step_by(4) {
+            // Where both start together, the downlink comes first
+            let mut directions = [Direction::Downlink, Direction::Uplink].into_iter();
+            if subslot < 20 {
+                directions.next();
+            }
+            for direction in directions {
+                let occurrence = occurrences.next().unwrap();
+                assert_eq!(occurrence.start, subslot * 14_400);
+                assert_eq!(occurrence.direction, direction);
+            }
+        }
+        assert_eq!(occurrences.next(), None);
+    }
+}
@@ -0,0 +1,210 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Route Info and Source Routing messages
+//!
+//! These are the payloads of the [`ROUTE_INFO`][numbers::mac_ie::ie6bit::ROUTE_INFO] and
+//! [`SOURCE_ROUTING`][numbers::mac_ie::ie6bit::SOURCE_ROUTING] IEs as defined in Sections 6.4.3.2
+//! and 6.4.3.16 of ETSI TS 103 636-4 V2.1.1, which are used in mesh operation.
+
+use super::{AnyIeType, SerializeIe};
+use crate::ParsingError;
+use ts_103_636_numbers as numbers;
+
+/// View on a Route Info message.
+///
+/// An FT announces through this the sink its route leads to, and how costly that route is.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RouteInfo<'buf>(&'buf [u8; 6]);
+
+impl<'buf> RouteInfo<'buf> {
+    /// Interprets the payload of a Route Info IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload is not 6 bytes long.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        Ok(Self(payload.try_into().map_err(|_| ParsingError)?))
+    }
+
+    /// The long RD ID of the sink the route leads to.
+    #[must_use]
+    pub fn sink_address(&self) -> u32 {
+        u32::from_be_bytes([self.0[0], self.0[1], self.0[2], self.0[3]])
+    }
+
+    /// The cost of the route to the sink.
+    #[must_use]
+    pub fn route_cost(&self) -> u8 {
+        self.0[4]
+    }
+
+    /// The sequence number of the application data last received from the sink.
+    #[must_use]
+    pub fn application_sequence_number(&self) -> u8 {
+        self.0[5]
+    }
+}
+
+/// Builder for a Route Info message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RouteInfoBuilder {
+    sink_address: u32,
+    route_cost: u8,
+    application_sequence_number: u8,
+}
+
+impl RouteInfoBuilder {
+    /// Creates a Route Info message.
+    #[must_use]
+    pub fn new(sink_address: u32, route_cost: u8, application_sequence_number: u8) -> Self {
+        Self {
+            sink_address,
+            route_cost,
+            application_sequence_number,
+        }
+    }
+}
+
+impl SerializeIe for RouteInfoBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::ROUTE_INFO);
+
+    fn payload_len(&self) -> usize {
+        6
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&self.sink_address.to_be_bytes())?;
+        w.write_all(&[self.route_cost, self.application_sequence_number])
+    }
+}
+
+/// View on a Source Routing message.
+///
+/// This accompanies data that is forwarded through the mesh towards a given destination.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SourceRouting<'buf>(&'buf [u8; 6]);
+
+impl<'buf> SourceRouting<'buf> {
+    /// Interprets the payload of a Source Routing IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload is not 6 bytes long.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        Ok(Self(payload.try_into().map_err(|_| ParsingError)?))
+    }
+
+    /// The number of hops the data has already taken.
+    #[must_use]
+    pub fn hop_count(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// The number of hops after which the data is discarded.
+    #[must_use]
+    pub fn hop_limit(&self) -> u8 {
+        self.0[1]
+    }
+
+    /// The long RD ID of the final destination.
+    #[must_use]
+    pub fn destination_address(&self) -> u32 {
+        u32::from_be_bytes([self.0[2], self.0[3], self.0[4], self.0[5]])
+    }
+}
+
+/// Builder for a Source Routing message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SourceRoutingBuilder {
+    hop_count: u8,
+    hop_limit: u8,
+    destination_address: u32,
+}
+
+impl SourceRoutingBuilder {
+    /// Creates a Source Routing message.
+    #[must_use]
+    pub fn new(hop_count: u8, hop_limit: u8, destination_address: u32) -> Self {
+        Self {
+            hop_count,
+            hop_limit,
+            destination_address,
+        }
+    }
+}
+
+impl From<&SourceRouting<'_>> for SourceRoutingBuilder {
+    fn from(input: &SourceRouting<'_>) -> Self {
+        Self::new(
+            input.hop_count(),
+            input.hop_limit(),
+            input.destination_address(),
+        )
+    }
+}
+
+impl SerializeIe for SourceRoutingBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::SOURCE_ROUTING);
+
+    fn payload_len(&self) -> usize {
+        6
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[self.hop_count, self.hop_limit])?;
+        w.write_all(&self.destination_address.to_be_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mac_ie::InformationElement;
+
+    #[test]
+    fn test_route_info() {
+        // Sink Address 0x12345678, Route Cost 3 and Application Sequence Number 200, in the field
+        // order of Section 6.4.3.2, in an IE without length field
+        let data = [0x11, 0x12, 0x34, 0x56, 0x78, 3, 200];
+        let mut remaining = &data[..];
+        let ie = InformationElement::parse(&mut remaining).unwrap();
+        assert!(remaining.is_empty());
+        assert!(ie.ie_number() == numbers::mac_ie::ie6bit::ROUTE_INFO);
+        let route_info = RouteInfo::parse(ie.payload()).unwrap();
+        assert_eq!(route_info.sink_address(), 0x1234_5678);
+        assert_eq!(route_info.route_cost(), 3);
+        assert_eq!(route_info.application_sequence_number(), 200);
+
+        let mut written = [0; 8];
+        RouteInfoBuilder::new(0x1234_5678, 3, 200)
+            .serialize(&mut &mut written[..])
+            .unwrap();
+        assert_eq!(written, [0x51, 6, 0x12, 0x34, 0x56, 0x78, 3, 200]);
+
+        RouteInfo::parse(&data[1..6]).unwrap_err();
+    }
+
+    #[test]
+    fn test_source_routing() {
+        // Hop Count 1, Hop Limit 4 and Destination Address 0xcafe0001, in the field order of
+        // Section 6.4.3.16, in an IE with 8-bit length
+        let data = [0x5a, 6, 1, 4, 0xca, 0xfe, 0x00, 0x01];
+        let mut remaining = &data[..];
+        let ie = InformationElement::parse(&mut remaining).unwrap();
+        assert!(ie.ie_number() == numbers::mac_ie::ie6bit::SOURCE_ROUTING);
+        let routing = SourceRouting::parse(ie.payload()).unwrap();
+        assert_eq!(routing.hop_count(), 1);
+        assert_eq!(routing.hop_limit(), 4);
+        assert_eq!(routing.destination_address(), 0xcafe_0001);
+
+        let mut written = [0; 8];
+        SourceRoutingBuilder::from(&routing)
+            .serialize(&mut &mut written[..])
+            .unwrap();
+        assert_eq!(written, data);
+    }
+}
@@ -0,0 +1,149 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! MAC Security Info message
+//!
+//! This is the payload of the [`MAC_SECURITY_INFO`][numbers::mac_ie::ie6bit::MAC_SECURITY_INFO]
+//! IE as defined in Section 6.4.3.1 of ETSI TS 103 636-4 V2.1.1. It leads the IEs of MAC PDUs
+//! whose MAC Security field is [`USED_WITH_IE`][numbers::mac_pdu::security::USED_WITH_IE], and
+//! is not ciphered itself; see [`Header::security`][crate::mac_pdu::Header::security].
+
+use super::{AnyIeType, SerializeIe};
+use crate::{ParsingError, ValueRangeError};
+use ts_103_636_numbers as numbers;
+
+/// The only version of the MAC security that is understood (Mode 1).
+const VERSION_MODE_1: u8 = 0;
+
+coded_enum! {
+    /// Security IV Type of a MAC Security Info message.
+    pub enum SecurityIvType {
+        /// The HPC is used once to build the IV.
+        OneTimeHpc = 0,
+        /// The HPC resynchronizes the receiver's expectation of the sender's HPC.
+        ResynchronizingHpc = 1,
+        /// The HPC is used once, and the receiver is asked to send its own HPC.
+        OneTimeHpcWithHpcRequest = 2,
+    }
+}
+
+/// View on a MAC Security Info message.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MacSecurityInfo<'buf>(&'buf [u8; 5]);
+
+impl<'buf> MacSecurityInfo<'buf> {
+    /// Interprets the payload of a MAC Security Info IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload is not 5 bytes long, if the version is not Mode 1, or if the
+    /// Security IV Type has a reserved value.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        let payload: &[u8; 5] = payload.try_into().map_err(|_| ParsingError)?;
+        if payload[0] >> 6 != VERSION_MODE_1 {
+            return Err(ParsingError);
+        }
+        SecurityIvType::from_code(payload[0] & 0x0f).ok_or(ParsingError)?;
+        Ok(Self(payload))
+    }
+
+    /// The 2-bit index of the key the PDU is secured with.
+    #[must_use]
+    pub fn key_index(&self) -> u8 {
+        (self.0[0] >> 4) & 0x03
+    }
+
+    /// How the HPC is to be used.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "Reserved values were excluded at parsing"
+    )]
+    #[must_use]
+    pub fn security_iv_type(&self) -> SecurityIvType {
+        SecurityIvType::from_code(self.0[0] & 0x0f).expect("Checked at parsing")
+    }
+
+    /// The sender's Hyper Packet Counter.
+    #[must_use]
+    pub fn hpc(&self) -> u32 {
+        u32::from_be_bytes([self.0[1], self.0[2], self.0[3], self.0[4]])
+    }
+}
+
+/// Builder for a MAC Security Info message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MacSecurityInfoBuilder {
+    key_index: u8,
+    security_iv_type: SecurityIvType,
+    hpc: u32,
+}
+
+impl MacSecurityInfoBuilder {
+    /// Creates a MAC Security Info message in Mode 1.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the key index exceeds 2 bits.
+    pub fn new(
+        key_index: u8,
+        security_iv_type: SecurityIvType,
+        hpc: u32,
+    ) -> Result<Self, ValueRangeError> {
+        if key_index > 0x03 {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            key_index,
+            security_iv_type,
+            hpc,
+        })
+    }
+
+    /// The Hyper Packet Counter.
+    #[must_use]
+    pub fn hpc(&self) -> u32 {
+        self.hpc
+    }
+}
+
+impl SerializeIe for MacSecurityInfoBuilder {
+    const IE_TYPE: AnyIeType = AnyIeType::Type6bit(numbers::mac_ie::ie6bit::MAC_SECURITY_INFO);
+
+    fn payload_len(&self) -> usize {
+        5
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[(VERSION_MODE_1 << 6)
+            | (self.key_index << 4)
+            | self.security_iv_type.code()])?;
+        w.write_all(&self.hpc.to_be_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut written = [0; 7];
+        MacSecurityInfoBuilder::new(1, SecurityIvType::ResynchronizingHpc, 0x0102_0304)
+            .unwrap()
+            .serialize(&mut &mut written[..])
+            .unwrap();
+        assert_eq!(written, [0x50, 5, 0x11, 1, 2, 3, 4]);
+
+        let info = MacSecurityInfo::parse(&written[2..]).unwrap();
+        assert_eq!(info.key_index(), 1);
+        assert_eq!(info.security_iv_type(), SecurityIvType::ResynchronizingHpc);
+        assert_eq!(info.hpc(), 0x0102_0304);
+
+        // Version other than Mode 1
+        MacSecurityInfo::parse(&[0x40, 1, 2, 3, 4]).unwrap_err();
+        // Reserved IV type
+        MacSecurityInfo::parse(&[0x03, 1, 2, 3, 4]).unwrap_err();
+        MacSecurityInfoBuilder::new(4, SecurityIvType::OneTimeHpc, 0).unwrap_err();
+    }
+}
@@ -0,0 +1,295 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Messages carried in short (5-bit) IEs
+//!
+//! These are the payloads of the
+//! [`KEEP_ALIVE`][numbers::mac_ie::ie5bit_len0::KEEP_ALIVE],
+//! [`CONFIGURATION_REQUEST`][numbers::mac_ie::ie5bit_len0::CONFIGURATION_REQUEST],
+//! [`RADIO_DEVICE_STATUS`][numbers::mac_ie::ie5bit_len1::RADIO_DEVICE_STATUS] and
+//! [`ASSOCIATION_CONTROL`][numbers::mac_ie::ie5bit_len1::ASSOCIATION_CONTROL] IEs as defined in
+//! Section 6.4.3 of ETSI TS 103 636-4 V2.1.1.
+//!
+//! The first two carry no payload; their types here only serve to parse and produce IEs of the
+//! right type.
+
+use super::{AnyIeType, SerializeIe};
+use crate::{ParsingError, ValueRangeError};
+use ts_103_636_numbers as numbers;
+
+/// The Keep Alive message, which has no payload.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct KeepAlive;
+
+impl KeepAlive {
+    /// Interprets the payload of a Keep Alive IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload is not empty.
+    pub fn parse(payload: &[u8]) -> Result<Self, ParsingError> {
+        if !payload.is_empty() {
+            return Err(ParsingError);
+        }
+        Ok(Self)
+    }
+}
+
+impl SerializeIe for KeepAlive {
+    const IE_TYPE: AnyIeType = AnyIeType::Type5bit(numbers::mac_ie::ie5bit_len0::KEEP_ALIVE);
+
+    fn payload_len(&self) -> usize {
+        0
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, _w: &mut W) -> Result<(), W::Error> {
+        Ok(())
+    }
+}
+
+/// The Configuration Request message, which has no payload.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConfigurationRequest;
+
+impl ConfigurationRequest {
+    /// Interprets the payload of a Configuration Request IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload is not empty.
+    pub fn parse(payload: &[u8]) -> Result<Self, ParsingError> {
+        if !payload.is_empty() {
+            return Err(ParsingError);
+        }
+        Ok(Self)
+    }
+}
+
+impl SerializeIe for ConfigurationRequest {
+    const IE_TYPE: AnyIeType =
+        AnyIeType::Type5bit(numbers::mac_ie::ie5bit_len0::CONFIGURATION_REQUEST);
+
+    fn payload_len(&self) -> usize {
+        0
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, _w: &mut W) -> Result<(), W::Error> {
+        Ok(())
+    }
+}
+
+coded_enum! {
+    /// Status Flag of a Radio Device Status message.
+    pub enum StatusFlag {
+        /// The device's memory is full; it can not receive data for the indicated duration.
+        MemoryFull = 1,
+        /// The device resumed normal operation.
+        NormalOperationResumed = 2,
+    }
+}
+
+/// Converts a coded Radio Device Status duration into milliseconds.
+///
+/// Returns `None` for the "unknown" value and reserved values.
+#[must_use]
+pub fn status_duration_ms(code: u8) -> Option<u32> {
+    const TABLE: [u32; 11] = [50, 100, 200, 400, 600, 800, 1000, 1500, 2000, 3000, 4000];
+    TABLE.get(usize::from(code)).copied()
+}
+
+/// View on a Radio Device Status message.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RadioDeviceStatus<'buf>(&'buf [u8; 1]);
+
+impl<'buf> RadioDeviceStatus<'buf> {
+    /// Interprets the payload of a Radio Device Status IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload is not 1 byte long, or if the status flag has a reserved value.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        let payload: &[u8; 1] = payload.try_into().map_err(|_| ParsingError)?;
+        StatusFlag::from_code((payload[0] >> 4) & 0x03).ok_or(ParsingError)?;
+        Ok(Self(payload))
+    }
+
+    /// The status the device reports.
+    #[expect(
+        clippy::missing_panics_doc,
+        reason = "Reserved values were excluded at parsing"
+    )]
+    #[must_use]
+    pub fn status_flag(&self) -> StatusFlag {
+        StatusFlag::from_code((self.0[0] >> 4) & 0x03).expect("Checked at parsing")
+    }
+
+    /// The coded duration of the status; see [`status_duration_ms()`].
+    #[must_use]
+    pub fn duration(&self) -> u8 {
+        self.0[0] & 0x0f
+    }
+}
+
+/// Builder for a Radio Device Status message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RadioDeviceStatusBuilder {
+    status_flag: StatusFlag,
+    duration: u8,
+}
+
+impl RadioDeviceStatusBuilder {
+    /// Creates a Radio Device Status message with a coded duration (see
+    /// [`status_duration_ms()`]).
+    ///
+    /// # Errors
+    ///
+    /// This errs if the duration exceeds 4 bits.
+    pub fn new(status_flag: StatusFlag, duration: u8) -> Result<Self, ValueRangeError> {
+        if duration > 0x0f {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            status_flag,
+            duration,
+        })
+    }
+}
+
+impl SerializeIe for RadioDeviceStatusBuilder {
+    const IE_TYPE: AnyIeType =
+        AnyIeType::Type5bit(numbers::mac_ie::ie5bit_len1::RADIO_DEVICE_STATUS);
+
+    fn payload_len(&self) -> usize {
+        1
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[(self.status_flag.code() << 4) | self.duration])
+    }
+}
+
+/// View on an Association Control message.
+///
+/// Through this, an FT tells an associated PT when to expect downlink data and how often it may
+/// send uplink data.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AssociationControl<'buf>(&'buf [u8; 1]);
+
+impl<'buf> AssociationControl<'buf> {
+    /// Interprets the payload of an Association Control IE.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the payload is not 1 byte long.
+    pub fn parse(payload: &'buf [u8]) -> Result<Self, ParsingError> {
+        Ok(Self(payload.try_into().map_err(|_| ParsingError)?))
+    }
+
+    /// Whether the PT needs to monitor the cluster beacons.
+    #[must_use]
+    pub fn cluster_beacon_monitoring(&self) -> bool {
+        self.0[0] >> 7 != 0
+    }
+
+    /// The coded 3-bit DL Data Reception field, indicating in which cluster beacon periods the PT
+    /// receives downlink data.
+    #[must_use]
+    pub fn dl_data_reception(&self) -> u8 {
+        (self.0[0] >> 4) & 0x07
+    }
+
+    /// The coded 3-bit UL Period field, indicating how often the PT may send uplink data.
+    #[must_use]
+    pub fn ul_period(&self) -> u8 {
+        self.0[0] & 0x07
+    }
+}
+
+/// Builder for an Association Control message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AssociationControlBuilder {
+    byte: u8,
+}
+
+impl AssociationControlBuilder {
+    /// Creates an Association Control message from its coded fields.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the DL data reception or UL period exceed 3 bits.
+    pub fn new(
+        cluster_beacon_monitoring: bool,
+        dl_data_reception: u8,
+        ul_period: u8,
+    ) -> Result<Self, ValueRangeError> {
+        if dl_data_reception > 0x07 || ul_period > 0x07 {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            byte: (u8::from(cluster_beacon_monitoring) << 7) | (dl_data_reception << 4) | ul_period,
+        })
+    }
+}
+
+impl SerializeIe for AssociationControlBuilder {
+    const IE_TYPE: AnyIeType =
+        AnyIeType::Type5bit(numbers::mac_ie::ie5bit_len1::ASSOCIATION_CONTROL);
+
+    fn payload_len(&self) -> usize {
+        1
+    }
+
+    fn serialize_payload<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[self.byte])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mac_ie::InformationElement;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut written = [0; 6];
+        let mut cursor = &mut written[..];
+        KeepAlive.serialize(&mut cursor).unwrap();
+        ConfigurationRequest.serialize(&mut cursor).unwrap();
+        RadioDeviceStatusBuilder::new(StatusFlag::MemoryFull, 6)
+            .unwrap()
+            .serialize(&mut cursor)
+            .unwrap();
+        AssociationControlBuilder::new(true, 2, 5)
+            .unwrap()
+            .serialize(&mut cursor)
+            .unwrap();
+        assert!(cursor.is_empty());
+        assert_eq!(written, [0xc2, 0xc1, 0xe1, 0x16, 0xe3, 0xa5]);
+
+        let mut ies = InformationElement::parse_stream(&written).map(Result::unwrap);
+        KeepAlive::parse(ies.next().unwrap().payload()).unwrap();
+        ConfigurationRequest::parse(ies.next().unwrap().payload()).unwrap();
+        let status = ies.next().unwrap();
+        assert!(
+            status.ie_number()
+                == AnyIeType::Type5bit(numbers::mac_ie::ie5bit_len1::RADIO_DEVICE_STATUS)
+        );
+        let status = RadioDeviceStatus::parse(status.payload()).unwrap();
+        assert_eq!(status.status_flag(), StatusFlag::MemoryFull);
+        assert_eq!(status_duration_ms(status.duration()), Some(1000));
+        let control = AssociationControl::parse(ies.next().unwrap().payload()).unwrap();
+        assert!(control.cluster_beacon_monitoring());
+        assert_eq!(control.dl_data_reception(), 2);
+        assert_eq!(control.ul_period(), 5);
+        assert!(ies.next().is_none());
+
+        KeepAlive::parse(&[0]).unwrap_err();
+        // Reserved status flag
+        RadioDeviceStatus::parse(&[0x30]).unwrap_err();
+    }
+}
@@ -0,0 +1,276 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Assembly of a MAC PDU that fills the PDC of a packet
+//!
+//! A PDC always carries a full transport block, so a MAC PDU that is shorter than the transport
+//! block size needs to be completed with Padding IEs (Section 6.4.3.8 of ETSI TS 103 636-4
+//! V2.1.1). The [`MacPduBuilder`] keeps track of the remaining space while the header and IEs are
+//! written, and closes the PDU with the fewest padding bytes of header overhead.
+
+use super::OwnedHeader;
+use crate::mac_ie::{InformationElement, SerializeIe};
+use crate::transport_block::{PacketLength, PdcFormat};
+use ts_103_636_numbers as numbers;
+
+/// Largest IE that can be written with a 16-bit length field.
+const MAX_IE_LEN: usize = 3 + u16::MAX as usize;
+
+/// An item was not written because it exceeds the space left in the PDU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DoesNotFit {
+    /// Length of the item, including its IE header.
+    pub needed: usize,
+    /// Space that was left in the PDU.
+    pub remaining: usize,
+}
+
+/// Error type of the writing methods of [`MacPduBuilder`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BuildError<E> {
+    /// The item does not fit; nothing was written, and the builder can still be used.
+    DoesNotFit(DoesNotFit),
+    /// The underlying writer failed.
+    Write(E),
+}
+
+/// Writer of a MAC PDU of fixed length.
+///
+/// The MAC header is written first through [`Self::header()`], followed by any number of IEs.
+/// [`Self::finish()`] then pads the PDU up to its capacity.
+///
+/// The builder writes into any [`embedded_io::Write`]r; a `&mut [u8]` or a `heapless::Vec` can be
+/// used to build PDUs without allocation. Writes happen as the items are added, so the writer
+/// should not be used for other data before the builder is finished.
+///
+/// For secured PDUs, the capacity excludes the MIC.
+#[derive(Debug)]
+pub struct MacPduBuilder<W> {
+    writer: W,
+    remaining: usize,
+}
+
+impl<W: embedded_io::Write> MacPduBuilder<W> {
+    /// Starts a MAC PDU that will be `capacity` bytes long.
+    #[must_use]
+    pub fn new(writer: W, capacity: usize) -> Self {
+        Self {
+            writer,
+            remaining: capacity,
+        }
+    }
+
+    /// Starts a MAC PDU that fills the transport block of a packet.
+    ///
+    /// The packet length and format are typically taken from the physical header through
+    /// [`PacketLength::from_pcc()`] and [`PdcFormat::from_pcc()`].
+    #[must_use]
+    pub fn for_packet(writer: W, format: &PdcFormat, packet_length: PacketLength) -> Self {
+        Self::new(writer, format.transport_block_size(packet_length))
+    }
+
+    /// Number of bytes that can still be written.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Largest payload that still fits into a 6-bit IE with length field.
+    ///
+    /// This accounts for the length field growing to 16 bits for longer payloads.
+    #[must_use]
+    pub fn max_payload_len(&self) -> usize {
+        match self.remaining {
+            0..=2 => 0,
+            3..=257 => self.remaining - 2,
+            _ => (self.remaining - 3).min(u16::MAX.into()),
+        }
+    }
+
+    fn reserve<E>(&mut self, needed: usize) -> Result<(), BuildError<E>> {
+        if needed > self.remaining {
+            return Err(BuildError::DoesNotFit(DoesNotFit {
+                needed,
+                remaining: self.remaining,
+            }));
+        }
+        self.remaining -= needed;
+        Ok(())
+    }
+
+    /// Writes the MAC header type and common header.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the header does not fit, or if the writer fails.
+    pub fn header(&mut self, header: &OwnedHeader) -> Result<(), BuildError<W::Error>> {
+        self.reserve(header.serialized_len())?;
+        header
+            .serialize(&mut self.writer)
+            .map_err(BuildError::Write)
+    }
+
+    /// Writes a typed IE, using the shortest length field that fits its payload.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the IE does not fit, or if the writer fails.
+    pub fn ie<I: SerializeIe>(&mut self, ie: &I) -> Result<(), BuildError<W::Error>> {
+        self.reserve(ie.ie_len())?;
+        ie.serialize(&mut self.writer).map_err(BuildError::Write)
+    }
+
+    /// Writes an untyped IE.
+    ///
+    /// IEs with 6-bit type should be created through
+    /// [`InformationElement::new_6bit_with_length()`], which picks the shortest length field.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the IE does not fit, or if the writer fails.
+    pub fn information_element(
+        &mut self,
+        ie: &InformationElement<'_>,
+    ) -> Result<(), BuildError<W::Error>> {
+        self.reserve(ie.ie_len())?;
+        ie.serialize(&mut self.writer).map_err(BuildError::Write)
+    }
+
+    /// Fills the remaining space with padding, and returns the writer.
+    ///
+    /// The padding is a single IE whenever the remaining space allows: a short Padding IE for 1
+    /// or 2 bytes, and a Padding IE with 8-bit or 16-bit length field otherwise. Padding IEs
+    /// without length field are not used, for they are not universally understood.
+    ///
+    /// # Errors
+    ///
+    /// This merely forwards any errors of the writer.
+    pub fn finish(mut self) -> Result<W, W::Error> {
+        while self.remaining > 0 {
+            let len = self.remaining.min(MAX_IE_LEN);
+            write_padding(&mut self.writer, len)?;
+            self.remaining -= len;
+        }
+        Ok(self.writer)
+    }
+}
+
+/// Writes a single Padding IE that is `len` bytes long in total.
+///
+/// `len` needs to be between 1 and [`MAX_IE_LEN`].
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "Lengths are checked by the match arms"
+)]
+fn write_padding<W: embedded_io::Write>(w: &mut W, len: usize) -> Result<(), W::Error> {
+    use numbers::mac_ie::{ie5bit_len0, ie5bit_len1, ie6bit};
+    use numbers::mac_pdu::mux_ext;
+
+    let payload_len = match len {
+        1 => {
+            w.write_all(&[ie5bit_len0::PADDING.composite() | (mux_ext::SHORT_IE << 6)])?;
+            0
+        }
+        2 => {
+            w.write_all(&[ie5bit_len1::PADDING.composite() | (mux_ext::SHORT_IE << 6)])?;
+            1
+        }
+        3..=257 => {
+            w.write_all(&[
+                u8::from(ie6bit::PADDING) | (mux_ext::LENGTH_8BIT << 6),
+                (len - 2) as u8,
+            ])?;
+            len - 2
+        }
+        _ => {
+            w.write_all(&[u8::from(ie6bit::PADDING) | (mux_ext::LENGTH_16BIT << 6)])?;
+            w.write_all(&((len - 3) as u16).to_be_bytes())?;
+            len - 3
+        }
+    };
+    let zeros = [0; 32];
+    let mut left = payload_len;
+    while left > 0 {
+        let chunk = left.min(zeros.len());
+        w.write_all(&zeros[..chunk])?;
+        left -= chunk;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mac_pdu::{Header, OwnedBeacon, OwnedMacCommonHeader, OwnedUnicast};
+
+    #[test]
+    fn test_build_and_pad() {
+        let header = OwnedHeader::new(OwnedMacCommonHeader::Unicast(
+            OwnedUnicast::new(false, 1, 0x1122_3344, 0x5566_7788).unwrap(),
+        ));
+        let data = [0xaa; 5];
+        let ie = InformationElement::new_6bit_with_length(
+            numbers::mac_ie::ie6bit::USER_PLANE_DATA_FLOW_1,
+            &data,
+        )
+        .unwrap();
+
+        // Remainders covering the short, 8-bit and 16-bit padding
+        for padding in [0, 1, 2, 3, 257, 258, 300] {
+            let capacity = 11 + 7 + padding;
+            let mut buf = [0xff; 400];
+            let mut builder = MacPduBuilder::new(&mut buf[..], capacity);
+            builder.header(&header).unwrap();
+            builder.information_element(&ie).unwrap();
+            assert_eq!(builder.remaining(), padding);
+            let rest = builder.finish().unwrap().len();
+            assert_eq!(rest, 400 - capacity);
+
+            let parsed = Header::parse(&buf[..capacity]).unwrap();
+            let mut items = parsed.tail_items();
+            assert_eq!(items.next().unwrap().unwrap().payload(), data);
+            match items.next() {
+                None => assert_eq!(padding, 0),
+                Some(padding_ie) => {
+                    let padding_ie = padding_ie.unwrap();
+                    assert_eq!(padding_ie.ie_len(), padding);
+                    assert!(padding_ie.payload().iter().all(|b| *b == 0));
+                }
+            }
+            assert!(items.next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_does_not_fit() {
+        let mut buf = [0; 32];
+        let mut builder = MacPduBuilder::new(&mut buf[..], 17);
+        builder
+            .header(&OwnedHeader::new(OwnedMacCommonHeader::Beacon(
+                OwnedBeacon::new(0x41_4141, 0x1234_5678).unwrap(),
+            )))
+            .unwrap();
+        assert_eq!(builder.max_payload_len(), 7);
+        let data = [0; 8];
+        let ie = InformationElement::new_6bit_with_length(
+            numbers::mac_ie::ie6bit::USER_PLANE_DATA_FLOW_1,
+            &data,
+        )
+        .unwrap();
+        assert_eq!(
+            builder.information_element(&ie),
+            Err(BuildError::DoesNotFit(DoesNotFit {
+                needed: 10,
+                remaining: 9
+            }))
+        );
+        let ie = InformationElement::new_6bit_with_length(
+            numbers::mac_ie::ie6bit::USER_PLANE_DATA_FLOW_1,
+            &data[..7],
+        )
+        .unwrap();
+        builder.information_element(&ie).unwrap();
+        assert_eq!(builder.finish().unwrap().len(), 15);
+    }
+}
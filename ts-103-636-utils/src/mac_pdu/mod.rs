@@ -0,0 +1,838 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Processing between a MAC PDU and its IEs (Information Elements)
+
+use super::{ParsingError, ValueRangeError};
+use crate::mac_ie::security_info::MacSecurityInfo;
+
+use ts_103_636_numbers as numbers;
+
+pub mod builder;
+
+/// The MAC header as defined in Section 6.3.2 of ETSI TS 103 636-4 V2.1.1.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MacHeaderType(pub u8);
+
+impl MacHeaderType {
+    /// The 2-bit Version field.
+    #[must_use]
+    pub fn version(&self) -> u8 {
+        self.0 >> 6
+    }
+
+    /// The 2-bit MAC Security field.
+    #[must_use]
+    pub fn mac_security(&self) -> u8 {
+        (self.0 >> 4) & 0x03
+    }
+
+    /// The 4-bit MAC Header Type field.
+    #[must_use]
+    pub fn mac_header_type(&self) -> u8 {
+        self.0 & 0x0f
+    }
+}
+
+#[derive(Debug)]
+pub struct DataMacPdu<'buf>(pub &'buf [u8; 2]);
+
+/// The DATA MAC PDU header as defined in Section 6.3.3.1 of ETSI TS 103 636-4 V2.1.1.
+impl DataMacPdu<'_> {
+    /// The single reset bit.
+    #[must_use]
+    pub fn reset(&self) -> bool {
+        ((self.0[0] >> 4) & 1) != 0
+    }
+
+    /// The 12 bit sequence number.
+    #[must_use]
+    pub fn sequence_number(&self) -> u16 {
+        (u16::from(self.0[0]) & 0x0f) << 8 | u16::from(self.0[1])
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for DataMacPdu<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "DataMacPdu {{ reset: {=bool}, sequence number: {=u16} }}",
+            self.reset(),
+            self.sequence_number(),
+        );
+    }
+}
+
+/// The Beacon header as defined in Section 6.3.3.2 of ETSI TS 103 636-4 V2.1.1.
+#[derive(Debug)]
+pub struct Beacon<'buf>(pub &'buf [u8; 7]);
+
+impl Beacon<'_> {
+    /// The 24 bit network ID.
+    #[must_use]
+    pub fn network_id(&self) -> u32 {
+        u32::from_be_bytes([0, self.0[0], self.0[1], self.0[2]])
+    }
+
+    #[must_use]
+    pub fn transmitter_address(&self) -> u32 {
+        #[allow(clippy::missing_panics_doc, reason = "panic is unreachable")]
+        u32::from_be_bytes(self.0[3..7].try_into().expect("sizes fit"))
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Beacon<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "Beacon {{ network id: {=u32}, transmitter address: {=u32} }}",
+            self.network_id(),
+            self.transmitter_address(),
+        );
+    }
+}
+
+/// The Unicast header as defined in Section 6.3.3.3 of ETSI TS 103 636-4 V2.1.1.
+#[derive(Debug)]
+pub struct Unicast<'buf>(pub &'buf [u8; 10]);
+
+impl Unicast<'_> {
+    /// The single reset bit.
+    #[must_use]
+    pub fn reset(&self) -> bool {
+        ((self.0[0] >> 4) & 1) != 0
+    }
+
+    /// The 12 bit sequence number.
+    ///
+    /// Note that Figure 6.3.3.3-1 of ETSI TS 103 636-4 V2.1.1 gives separate names to this field,
+    /// but the prose speaks of a 12-bit sequence number.
+    #[must_use]
+    pub fn sequence_number(&self) -> u16 {
+        (u16::from(self.0[0]) & 0x0f) << 8 | u16::from(self.0[1])
+    }
+
+    /// The 32 bit receiver address.
+    #[must_use]
+    pub fn receiver_address(&self) -> u32 {
+        #[allow(clippy::missing_panics_doc, reason = "panic is unreachable")]
+        u32::from_be_bytes(self.0[2..6].try_into().expect("sizes fit"))
+    }
+
+    /// The 32 bit transmitter address.
+    #[must_use]
+    pub fn transmitter_address(&self) -> u32 {
+        #[allow(clippy::missing_panics_doc, reason = "panic is unreachable")]
+        u32::from_be_bytes(self.0[6..10].try_into().expect("sizes fit"))
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Unicast<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "Unicast {{ reset: {=bool}, sequence number: {=u16}, receiver address: {=u32}, transmitter address: {=u32} }}",
+            self.reset(),
+            self.sequence_number(),
+            self.receiver_address(),
+            self.transmitter_address(),
+        );
+    }
+}
+
+/// The RD Broadcasting header as defined in Section 6.3.3.4 of ETSI TS 103 636-4 V2.1.1.
+#[derive(Debug)]
+pub struct RdBroadcast<'buf>(pub &'buf [u8; 6]);
+
+impl RdBroadcast<'_> {
+    /// The single reset bit.
+    #[must_use]
+    pub fn reset(&self) -> bool {
+        ((self.0[0] >> 4) & 1) != 0
+    }
+
+    /// The 12 bit sequence number.
+    #[must_use]
+    pub fn sequence_number(&self) -> u16 {
+        (u16::from(self.0[0]) & 0x0f) << 8 | u16::from(self.0[1])
+    }
+
+    /// The 32 bit transmitter address.
+    #[must_use]
+    pub fn transmitter_address(&self) -> u32 {
+        #[allow(clippy::missing_panics_doc, reason = "panic is unreachable")]
+        u32::from_be_bytes(self.0[2..6].try_into().expect("sizes fit"))
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for RdBroadcast<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "RdBroadcast {{ reset: {=bool}, sequence number: {=u16}, transmitter address: {=u32} }}",
+            self.reset(),
+            self.sequence_number(),
+            self.transmitter_address(),
+        );
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MacCommonHeader<'buf> {
+    DataMacPdu(DataMacPdu<'buf>),
+    Beacon(Beacon<'buf>),
+    Unicast(Unicast<'buf>),
+    RdBroadcast(RdBroadcast<'buf>),
+}
+
+impl<'buf> MacCommonHeader<'buf> {
+    /// The common header as it was received.
+    #[must_use]
+    pub fn as_bytes(&self) -> &'buf [u8] {
+        match self {
+            Self::DataMacPdu(inner) => inner.0,
+            Self::Beacon(inner) => inner.0,
+            Self::Unicast(inner) => inner.0,
+            Self::RdBroadcast(inner) => inner.0,
+        }
+    }
+}
+
+/// Length of the MIC that ends a secured MAC PDU.
+pub const MIC_LEN: usize = 5;
+
+/// The tail of a MAC PDU that has MAC security applied, split into its parts.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SecuredTail<'buf> {
+    /// The MAC Security Info IE that leads the tail, if the MAC Security field is
+    /// [`USED_WITH_IE`][numbers::mac_pdu::security::USED_WITH_IE].
+    pub security_info: Option<MacSecurityInfo<'buf>>,
+    /// The ciphered IEs.
+    pub ciphered: &'buf [u8],
+    /// The Message Integrity Code.
+    pub mic: &'buf [u8; MIC_LEN],
+}
+
+impl<'buf> SecuredTail<'buf> {
+    fn parse(mac_security: u8, mut tail: &'buf [u8]) -> Result<Self, ParsingError> {
+        let security_info = if mac_security == numbers::mac_pdu::security::USED_WITH_IE {
+            let ie = crate::mac_ie::InformationElement::parse(&mut tail)?;
+            if ie.ie_number() != numbers::mac_ie::ie6bit::MAC_SECURITY_INFO {
+                return Err(ParsingError);
+            }
+            Some(MacSecurityInfo::parse(ie.payload())?)
+        } else {
+            None
+        };
+        let (ciphered, mic) = tail.split_last_chunk::<MIC_LEN>().ok_or(ParsingError)?;
+        Ok(Self {
+            security_info,
+            ciphered,
+            mic,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Header<'buf> {
+    pub head: MacHeaderType,
+    pub common: MacCommonHeader<'buf>,
+    /// IE items; best used with the [`Self::tail_items()`] iterator
+    ///
+    /// If MAC security is applied, this is not plaintext; see [`Self::security`] instead.
+    pub tail: &'buf [u8],
+    /// The parts of the tail, if MAC security is applied.
+    pub security: Option<SecuredTail<'buf>>,
+}
+
+impl<'buf> Header<'buf> {
+    /// Parses a buffer as a MAC PDU, with a header indicating the common header type, a common
+    /// header, and a tail of IEs.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the MAC version is not the one specified and understood, on various length
+    /// errors, or if a secured PDU has a reserved MAC Security value or lacks its MAC Security Info
+    /// IE or MIC.
+    #[expect(clippy::missing_panics_doc, reason = "panics are unreachable")]
+    pub fn parse(mut buffer: &'buf [u8]) -> Result<Self, ParsingError> {
+        let head = *buffer.split_off_first().ok_or(ParsingError)?;
+        let head = MacHeaderType(head);
+        if head.version() != numbers::mac_pdu::VERSION {
+            // version could have different structure
+            return Err(ParsingError);
+        }
+
+        let common = match head.mac_header_type() {
+            numbers::mac_pdu::header_type::DATA_MAC_PDU => MacCommonHeader::DataMacPdu(DataMacPdu(
+                buffer
+                    .split_off(..2)
+                    .ok_or(ParsingError)?
+                    .try_into()
+                    .expect("split_off length is guaranteed"),
+            )),
+            numbers::mac_pdu::header_type::BEACON => MacCommonHeader::Beacon(Beacon(
+                buffer
+                    .split_off(..7)
+                    .ok_or(ParsingError)?
+                    .try_into()
+                    .expect("split_off length is guaranteed"),
+            )),
+            numbers::mac_pdu::header_type::UNICAST => MacCommonHeader::Unicast(Unicast(
+                buffer
+                    .split_off(..10)
+                    .ok_or(ParsingError)?
+                    .try_into()
+                    .expect("split_off length is guaranteed"),
+            )),
+            numbers::mac_pdu::header_type::RD_BROADCAST => {
+                MacCommonHeader::RdBroadcast(RdBroadcast(
+                    buffer
+                        .split_off(..6)
+                        .ok_or(ParsingError)?
+                        .try_into()
+                        .expect("split_off length is guaranteed"),
+                ))
+            }
+            _ => return Err(ParsingError),
+        };
+
+        let security = match head.mac_security() {
+            numbers::mac_pdu::security::NOTUSED => None,
+            mac_security @ (numbers::mac_pdu::security::USED_NO_IE
+            | numbers::mac_pdu::security::USED_WITH_IE) => {
+                Some(SecuredTail::parse(mac_security, buffer)?)
+            }
+            _ => return Err(ParsingError),
+        };
+
+        Ok(Self {
+            head,
+            common,
+            tail: buffer,
+            security,
+        })
+    }
+
+    /// Convenience accessor for
+    /// [`InformationElement::parse_stream(Self::tail)`][crate::mac_ie::InformationElement::parse_stream].
+    ///
+    /// If MAC security is applied, the IEs are not available in plaintext; the iterator then
+    /// produces a single error.
+    pub fn tail_items(
+        &self,
+    ) -> impl Iterator<Item = Result<crate::mac_ie::InformationElement<'_>, ParsingError>> {
+        let secured = self.security.is_some();
+        let plaintext = if secured { &[][..] } else { self.tail };
+        secured
+            .then_some(Err(ParsingError))
+            .into_iter()
+            .chain(crate::mac_ie::InformationElement::parse_stream(plaintext))
+    }
+
+    /// The Reset flag and Sequence Number as sent in the first 2 bytes of the common header.
+    ///
+    /// Together with the HPC, these form the packet counter material from which the IV of a
+    /// secured PDU is built. Beacon headers carry no sequence number, and return `None`.
+    #[must_use]
+    pub fn sequence_bytes(&self) -> Option<[u8; 2]> {
+        match &self.common {
+            MacCommonHeader::DataMacPdu(inner) => Some(*inner.0),
+            MacCommonHeader::Beacon(_) => None,
+            MacCommonHeader::Unicast(inner) => Some([inner.0[0], inner.0[1]]),
+            MacCommonHeader::RdBroadcast(inner) => Some([inner.0[0], inner.0[1]]),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Header<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "Header {{ .head.mac security: {=u8}, .common: ",
+            self.head.mac_security(),
+        );
+        match &self.common {
+            MacCommonHeader::DataMacPdu(inner) => inner.format(fmt),
+            MacCommonHeader::Beacon(inner) => inner.format(fmt),
+            MacCommonHeader::Unicast(inner) => inner.format(fmt),
+            MacCommonHeader::RdBroadcast(inner) => inner.format(fmt),
+        }
+        if let Some(security) = &self.security {
+            defmt::write!(fmt, ", security: {} }}", security);
+            return;
+        }
+        defmt::write!(fmt, ", IEs: [");
+        let mut first = true;
+        for ie in self.tail_items() {
+            if first {
+                first = false;
+            } else {
+                defmt::write!(fmt, ", ");
+            }
+            if let Ok(ie) = ie {
+                ie.format(fmt);
+            } else {
+                defmt::write!(fmt, "unparsable; full tail is {=[u8]:02x}", self.tail);
+                break;
+            }
+        }
+        defmt::write!(fmt, "] }}");
+    }
+}
+
+/// Owned counterpart of [`DataMacPdu`], used for building PDUs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OwnedDataMacPdu {
+    reset: bool,
+    sequence_number: u16,
+}
+
+impl OwnedDataMacPdu {
+    /// Creates a DATA MAC PDU header.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the sequence number exceeds 12 bits.
+    pub fn new(reset: bool, sequence_number: u16) -> Result<Self, ValueRangeError> {
+        if sequence_number > 0x0fff {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            reset,
+            sequence_number,
+        })
+    }
+
+    /// Serializes into any [`embedded_io::Write`]r.
+    ///
+    /// # Errors
+    ///
+    /// This merely forwards any errors of the writer.
+    pub fn serialize<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&reset_and_sequence_number(self.reset, self.sequence_number))
+    }
+}
+
+impl From<&DataMacPdu<'_>> for OwnedDataMacPdu {
+    fn from(value: &DataMacPdu<'_>) -> Self {
+        Self {
+            reset: value.reset(),
+            sequence_number: value.sequence_number(),
+        }
+    }
+}
+
+/// Owned counterpart of [`Beacon`], used for building PDUs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OwnedBeacon {
+    network_id: u32,
+    transmitter_address: u32,
+}
+
+impl OwnedBeacon {
+    /// Creates a Beacon header.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the network ID exceeds 24 bits.
+    pub fn new(network_id: u32, transmitter_address: u32) -> Result<Self, ValueRangeError> {
+        if network_id > 0x00ff_ffff {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            network_id,
+            transmitter_address,
+        })
+    }
+
+    /// Serializes into any [`embedded_io::Write`]r.
+    ///
+    /// # Errors
+    ///
+    /// This merely forwards any errors of the writer.
+    pub fn serialize<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&self.network_id.to_be_bytes()[1..])?;
+        w.write_all(&self.transmitter_address.to_be_bytes())
+    }
+}
+
+impl From<&Beacon<'_>> for OwnedBeacon {
+    fn from(value: &Beacon<'_>) -> Self {
+        Self {
+            network_id: value.network_id(),
+            transmitter_address: value.transmitter_address(),
+        }
+    }
+}
+
+/// Owned counterpart of [`Unicast`], used for building PDUs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OwnedUnicast {
+    reset: bool,
+    sequence_number: u16,
+    receiver_address: u32,
+    transmitter_address: u32,
+}
+
+impl OwnedUnicast {
+    /// Creates a Unicast header.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the sequence number exceeds 12 bits.
+    pub fn new(
+        reset: bool,
+        sequence_number: u16,
+        receiver_address: u32,
+        transmitter_address: u32,
+    ) -> Result<Self, ValueRangeError> {
+        if sequence_number > 0x0fff {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            reset,
+            sequence_number,
+            receiver_address,
+            transmitter_address,
+        })
+    }
+
+    /// Serializes into any [`embedded_io::Write`]r.
+    ///
+    /// # Errors
+    ///
+    /// This merely forwards any errors of the writer.
+    pub fn serialize<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&reset_and_sequence_number(self.reset, self.sequence_number))?;
+        w.write_all(&self.receiver_address.to_be_bytes())?;
+        w.write_all(&self.transmitter_address.to_be_bytes())
+    }
+}
+
+impl From<&Unicast<'_>> for OwnedUnicast {
+    fn from(value: &Unicast<'_>) -> Self {
+        Self {
+            reset: value.reset(),
+            sequence_number: value.sequence_number(),
+            receiver_address: value.receiver_address(),
+            transmitter_address: value.transmitter_address(),
+        }
+    }
+}
+
+/// Owned counterpart of [`RdBroadcast`], used for building PDUs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OwnedRdBroadcast {
+    reset: bool,
+    sequence_number: u16,
+    transmitter_address: u32,
+}
+
+impl OwnedRdBroadcast {
+    /// Creates an RD Broadcasting header.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the sequence number exceeds 12 bits.
+    pub fn new(
+        reset: bool,
+        sequence_number: u16,
+        transmitter_address: u32,
+    ) -> Result<Self, ValueRangeError> {
+        if sequence_number > 0x0fff {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            reset,
+            sequence_number,
+            transmitter_address,
+        })
+    }
+
+    /// Serializes into any [`embedded_io::Write`]r.
+    ///
+    /// # Errors
+    ///
+    /// This merely forwards any errors of the writer.
+    pub fn serialize<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&reset_and_sequence_number(self.reset, self.sequence_number))?;
+        w.write_all(&self.transmitter_address.to_be_bytes())
+    }
+}
+
+impl From<&RdBroadcast<'_>> for OwnedRdBroadcast {
+    fn from(value: &RdBroadcast<'_>) -> Self {
+        Self {
+            reset: value.reset(),
+            sequence_number: value.sequence_number(),
+            transmitter_address: value.transmitter_address(),
+        }
+    }
+}
+
+/// Encodes the first two bytes shared by all common headers that have a sequence number.
+///
+/// The sequence number needs to be checked to fit in 12 bits by the caller.
+fn reset_and_sequence_number(reset: bool, sequence_number: u16) -> [u8; 2] {
+    let [high, low] = sequence_number.to_be_bytes();
+    [(u8::from(reset) << 4) | high, low]
+}
+
+/// Owned counterpart of [`MacCommonHeader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OwnedMacCommonHeader {
+    DataMacPdu(OwnedDataMacPdu),
+    Beacon(OwnedBeacon),
+    Unicast(OwnedUnicast),
+    RdBroadcast(OwnedRdBroadcast),
+}
+
+impl OwnedMacCommonHeader {
+    /// The value of the MAC Header Type field that announces this common header.
+    #[must_use]
+    pub fn mac_header_type(&self) -> u8 {
+        match self {
+            Self::DataMacPdu(_) => numbers::mac_pdu::header_type::DATA_MAC_PDU,
+            Self::Beacon(_) => numbers::mac_pdu::header_type::BEACON,
+            Self::Unicast(_) => numbers::mac_pdu::header_type::UNICAST,
+            Self::RdBroadcast(_) => numbers::mac_pdu::header_type::RD_BROADCAST,
+        }
+    }
+
+    /// Number of bytes written by [`Self::serialize()`].
+    #[must_use]
+    pub fn serialized_len(&self) -> usize {
+        match self {
+            Self::DataMacPdu(_) => 2,
+            Self::Beacon(_) => 7,
+            Self::Unicast(_) => 10,
+            Self::RdBroadcast(_) => 6,
+        }
+    }
+
+    /// Serializes into any [`embedded_io::Write`]r.
+    ///
+    /// # Errors
+    ///
+    /// This merely forwards any errors of the writer.
+    pub fn serialize<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        match self {
+            Self::DataMacPdu(inner) => inner.serialize(w),
+            Self::Beacon(inner) => inner.serialize(w),
+            Self::Unicast(inner) => inner.serialize(w),
+            Self::RdBroadcast(inner) => inner.serialize(w),
+        }
+    }
+}
+
+impl From<&MacCommonHeader<'_>> for OwnedMacCommonHeader {
+    fn from(value: &MacCommonHeader<'_>) -> Self {
+        match value {
+            MacCommonHeader::DataMacPdu(inner) => Self::DataMacPdu(inner.into()),
+            MacCommonHeader::Beacon(inner) => Self::Beacon(inner.into()),
+            MacCommonHeader::Unicast(inner) => Self::Unicast(inner.into()),
+            MacCommonHeader::RdBroadcast(inner) => Self::RdBroadcast(inner.into()),
+        }
+    }
+}
+
+/// Owned counterpart of the leading part of [`Header`] (everything but the IEs).
+///
+/// Serializing this produces the MAC header type and the common header; IEs are then appended by
+/// the user, e.g. through [`InformationElement::serialize`][crate::mac_ie::InformationElement::serialize].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OwnedHeader {
+    mac_security: u8,
+    common: OwnedMacCommonHeader,
+}
+
+impl OwnedHeader {
+    /// Creates a header with the current MAC version and no MAC security.
+    #[must_use]
+    pub fn new(common: OwnedMacCommonHeader) -> Self {
+        Self {
+            mac_security: numbers::mac_pdu::security::NOTUSED,
+            common,
+        }
+    }
+
+    /// Sets the MAC Security field to any of the [`numbers::mac_pdu::security`] values.
+    ///
+    /// The serialized header is then to be followed by the secured tail as described in
+    /// [`SecuredTail`].
+    ///
+    /// # Errors
+    ///
+    /// This errs if the value exceeds 2 bits.
+    pub fn mac_security(mut self, mac_security: u8) -> Result<Self, ValueRangeError> {
+        if mac_security > 0x03 {
+            return Err(ValueRangeError);
+        }
+        self.mac_security = mac_security;
+        Ok(self)
+    }
+
+    /// The MAC header type byte that is sent first.
+    #[must_use]
+    pub fn head(&self) -> MacHeaderType {
+        MacHeaderType(
+            (numbers::mac_pdu::VERSION << 6)
+                | (self.mac_security << 4)
+                | self.common.mac_header_type(),
+        )
+    }
+
+    /// The common header.
+    #[must_use]
+    pub fn common(&self) -> &OwnedMacCommonHeader {
+        &self.common
+    }
+
+    /// Number of bytes written by [`Self::serialize()`].
+    #[must_use]
+    pub fn serialized_len(&self) -> usize {
+        1 + self.common.serialized_len()
+    }
+
+    /// Serializes into any [`embedded_io::Write`]r.
+    ///
+    /// # Errors
+    ///
+    /// This merely forwards any errors of the writer.
+    pub fn serialize<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+        w.write_all(&[self.head().0])?;
+        self.common.serialize(w)
+    }
+}
+
+impl From<&Header<'_>> for OwnedHeader {
+    fn from(value: &Header<'_>) -> Self {
+        Self {
+            mac_security: value.head.mac_security(),
+            common: (&value.common).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let beacon = &[
+            1, 18, 52, 86, 0, 0, 0, 38, 73, 5, 176, 16, 6, 0, 13, 83, 7, 8, 12, 138, 160, 215, 2,
+            100, 64, 24, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+
+        let beacon = Header::parse(&beacon[..]).unwrap();
+        let MacCommonHeader::Beacon(common) = beacon.common else {
+            panic!("Test vector encodes a beacon but was not recognized as such");
+        };
+        assert_eq!(common.network_id(), 0x0012_3456);
+        assert_eq!(common.transmitter_address(), 0x26);
+        // Detailed parsing of that very string is tested in mac_ie.rs
+        assert!(matches!(beacon.tail, [73, 5, .., 0]));
+    }
+
+    #[test]
+    fn test_serialize_header() {
+        let beacon = &[
+            1, 18, 52, 86, 0, 0, 0, 38, 73, 5, 176, 16, 6, 0, 13, 83, 7, 8, 12, 138, 160, 215, 2,
+            100, 64, 24, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+
+        let parsed = Header::parse(&beacon[..]).unwrap();
+        let owned = OwnedHeader::from(&parsed);
+        assert_eq!(
+            owned,
+            OwnedHeader::new(OwnedMacCommonHeader::Beacon(
+                OwnedBeacon::new(0x0012_3456, 0x26).unwrap()
+            ))
+        );
+
+        let mut written = [0; 8];
+        owned.serialize(&mut &mut written[..]).unwrap();
+        assert_eq!(written, beacon[..8]);
+
+        OwnedBeacon::new(0x0100_0000, 0).unwrap_err();
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let headers = [
+            OwnedMacCommonHeader::DataMacPdu(OwnedDataMacPdu::new(true, 0xabc).unwrap()),
+            OwnedMacCommonHeader::Unicast(
+                OwnedUnicast::new(false, 0x123, 0x1122_3344, 0x5566_7788).unwrap(),
+            ),
+            OwnedMacCommonHeader::RdBroadcast(
+                OwnedRdBroadcast::new(true, 0xfff, 0x1234_5678).unwrap(),
+            ),
+        ];
+        for common in headers {
+            let owned = OwnedHeader::new(common);
+            let mut buffer = [0; 16];
+            let mut writer = &mut buffer[..];
+            owned.serialize(&mut writer).unwrap();
+            let remaining = writer.len();
+            let written = &buffer[..buffer.len() - remaining];
+
+            let parsed = Header::parse(written).unwrap();
+            assert_eq!(OwnedHeader::from(&parsed), owned);
+            assert!(parsed.tail.is_empty());
+        }
+
+        OwnedDataMacPdu::new(false, 0x1000).unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_secured() {
+        let owned = OwnedHeader::new(OwnedMacCommonHeader::Unicast(
+            OwnedUnicast::new(true, 0x123, 0x1122_3344, 0x5566_7788).unwrap(),
+        ))
+        .mac_security(numbers::mac_pdu::security::USED_WITH_IE)
+        .unwrap();
+        let mut pdu = [0; 25];
+        owned.serialize(&mut &mut pdu[..]).unwrap();
+        // MAC Security Info IE without length field, 3 ciphered bytes and the MIC
+        pdu[11..].copy_from_slice(&[0x10, 0x00, 0, 0, 0, 42, 0xa, 0xb, 0xc, 1, 2, 3, 4, 5]);
+
+        let parsed = Header::parse(&pdu).unwrap();
+        assert_eq!(OwnedHeader::from(&parsed), owned);
+        assert_eq!(parsed.sequence_bytes(), Some([0x11, 0x23]));
+        let security = parsed.security.as_ref().unwrap();
+        assert_eq!(security.security_info.as_ref().unwrap().hpc(), 42);
+        assert_eq!(security.ciphered, [0xa, 0xb, 0xc]);
+        assert_eq!(security.mic, &[1, 2, 3, 4, 5]);
+        assert_eq!(parsed.tail_items().count(), 1);
+        parsed.tail_items().next().unwrap().unwrap_err();
+
+        // Without the IE, everything up to the MIC is ciphered
+        pdu[0] = 0x12;
+        let parsed = Header::parse(&pdu).unwrap();
+        let security = parsed.security.unwrap();
+        assert!(security.security_info.is_none());
+        assert_eq!(security.ciphered.len(), 9);
+
+        // Too short for a MIC
+        Header::parse(&pdu[..15]).unwrap_err();
+        // Lacking the MAC Security Info IE
+        pdu[0] = 0x22;
+        pdu[11] = 0x11;
+        Header::parse(&pdu).unwrap_err();
+    }
+}
@@ -0,0 +1,616 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Processing of the Physical Layer Control field (PCC), i.e. the physical header
+//!
+//! The physical header comes in two sizes: Type 1 (5 bytes) and Type 2 (10 bytes), as defined in
+//! Section 6.2 of ETSI TS 103 636-4 V2.1.1. Which one is used is not expressed inside the header,
+//! but signalled by the PHY (and, on the nRF91 modem API, by the `phy_type` field).
+
+use super::{ParsingError, ValueRangeError};
+
+/// Unit in which [`Type1::packet_length()`] and [`Type2::packet_length()`] are expressed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PacketLengthType {
+    Subslots,
+    Slots,
+}
+
+impl PacketLengthType {
+    pub(crate) fn from_bit(bit: u8) -> Self {
+        if bit == 0 {
+            Self::Subslots
+        } else {
+            Self::Slots
+        }
+    }
+
+    pub(crate) fn bit(self) -> u8 {
+        match self {
+            Self::Subslots => 0,
+            Self::Slots => 1,
+        }
+    }
+}
+
+/// Converts a 4-bit transmit power code into dBm.
+///
+/// This follows Table 6.2.1-3a of ETSI TS 103 636-4 V2.1.1; the same coding is used for the
+/// "Clusters Max TX power" fields of the beacon IEs.
+///
+/// Bits above the lowest 4 are ignored.
+#[must_use]
+pub fn transmit_power_dbm(code: u8) -> i8 {
+    const TABLE: [i8; 16] = [
+        -40, -30, -20, -16, -12, -8, -4, 0, 4, 7, 10, 13, 16, 19, 21, 23,
+    ];
+    TABLE[usize::from(code & 0x0f)]
+}
+
+/// Fields shared by the Type 1 and Type 2 header in their first 4 bytes.
+///
+/// Doing this through a macro rather than a trait keeps the accessors inherent.
+macro_rules! common_accessors {
+    () => {
+        /// The 3-bit Header Format field.
+        #[must_use]
+        pub fn header_format(&self) -> u8 {
+            self.0[0] >> 5
+        }
+
+        /// The unit of [`Self::packet_length()`].
+        #[must_use]
+        pub fn packet_length_type(&self) -> PacketLengthType {
+            PacketLengthType::from_bit((self.0[0] >> 4) & 1)
+        }
+
+        /// The packet length in units of [`Self::packet_length_type()`].
+        ///
+        /// The 4-bit field encodes the length minus one, so this is in the range 1 to 16.
+        #[must_use]
+        pub fn packet_length(&self) -> u8 {
+            (self.0[0] & 0x0f) + 1
+        }
+
+        /// The 8-bit Short Network ID (the lowest 8 bits of the Network ID).
+        #[must_use]
+        pub fn short_network_id(&self) -> u8 {
+            self.0[1]
+        }
+
+        /// The 16-bit Transmitter Identity (the Short Radio Device ID of the sender).
+        #[must_use]
+        pub fn transmitter_identity(&self) -> u16 {
+            u16::from_be_bytes([self.0[2], self.0[3]])
+        }
+
+        /// The 4-bit Transmit Power field; see [`transmit_power_dbm()`] for its meaning.
+        #[must_use]
+        pub fn transmit_power(&self) -> u8 {
+            self.0[4] >> 4
+        }
+    };
+}
+
+/// A Type 1 (5 byte) physical header as defined in Section 6.2.1 of ETSI TS 103 636-4 V2.1.1.
+#[derive(Debug)]
+pub struct Type1<'buf>(pub &'buf [u8; 5]);
+
+impl Type1<'_> {
+    common_accessors!();
+
+    /// The 3-bit DF MCS field.
+    ///
+    /// The bit between the Transmit Power and this field is reserved.
+    #[must_use]
+    pub fn df_mcs(&self) -> u8 {
+        self.0[4] & 0x07
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Type1<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "Type1 {{ format: {=u8}, length: {=u8} {}, short network id: {=u8:#04x}, transmitter: {=u16}, tx power: {=u8}, df mcs: {=u8} }}",
+            self.header_format(),
+            self.packet_length(),
+            self.packet_length_type(),
+            self.short_network_id(),
+            self.transmitter_identity(),
+            self.transmit_power(),
+            self.df_mcs(),
+        );
+    }
+}
+
+/// A Type 2 (10 byte) physical header as defined in Section 6.2.1 of ETSI TS 103 636-4 V2.1.1.
+#[derive(Debug)]
+pub struct Type2<'buf>(pub &'buf [u8; 10]);
+
+impl Type2<'_> {
+    common_accessors!();
+
+    /// The 4-bit DF MCS field.
+    #[must_use]
+    pub fn df_mcs(&self) -> u8 {
+        self.0[4] & 0x0f
+    }
+
+    /// The 16-bit Receiver Identity (the Short Radio Device ID of the recipient).
+    #[must_use]
+    pub fn receiver_identity(&self) -> u16 {
+        u16::from_be_bytes([self.0[5], self.0[6]])
+    }
+
+    /// The 2-bit Number of Spatial Streams field (in its coded form, 0 being a single stream).
+    #[must_use]
+    pub fn number_of_spatial_streams(&self) -> u8 {
+        self.0[7] >> 6
+    }
+
+    /// The 2-bit DF Redundancy Version.
+    ///
+    /// This is only present in header format 000 (the HARQ format).
+    #[must_use]
+    pub fn df_redundancy_version(&self) -> Option<u8> {
+        self.has_harq().then(|| (self.0[7] >> 4) & 0x03)
+    }
+
+    /// The single DF New Data Indication bit.
+    ///
+    /// This is only present in header format 000 (the HARQ format).
+    #[must_use]
+    pub fn df_new_data_indication(&self) -> Option<bool> {
+        self.has_harq().then(|| (self.0[7] >> 3) & 1 != 0)
+    }
+
+    /// The 3-bit DF HARQ Process Number.
+    ///
+    /// This is only present in header format 000 (the HARQ format).
+    #[must_use]
+    pub fn df_harq_process_number(&self) -> Option<u8> {
+        self.has_harq().then(|| self.0[7] & 0x07)
+    }
+
+    /// The 4-bit Feedback Format field.
+    ///
+    /// The value 0 indicates that no feedback is sent.
+    #[must_use]
+    pub fn feedback_format(&self) -> u8 {
+        self.0[8] >> 4
+    }
+
+    /// The 12-bit Feedback Info field, whose interpretation depends on
+    /// [`Self::feedback_format()`].
+    #[must_use]
+    pub fn feedback_info(&self) -> u16 {
+        u16::from_be_bytes([self.0[8] & 0x0f, self.0[9]])
+    }
+
+    fn has_harq(&self) -> bool {
+        self.header_format() == 0
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Type2<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "Type2 {{ format: {=u8}, length: {=u8} {}, short network id: {=u8:#04x}, transmitter: {=u16}, receiver: {=u16}, tx power: {=u8}, df mcs: {=u8}, feedback format: {=u8}, feedback info: {=u16:#05x} }}",
+            self.header_format(),
+            self.packet_length(),
+            self.packet_length_type(),
+            self.short_network_id(),
+            self.transmitter_identity(),
+            self.receiver_identity(),
+            self.transmit_power(),
+            self.df_mcs(),
+            self.feedback_format(),
+            self.feedback_info(),
+        );
+    }
+}
+
+/// A physical header of either size.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Pcc<'buf> {
+    Type1(Type1<'buf>),
+    Type2(Type2<'buf>),
+}
+
+impl<'buf> Pcc<'buf> {
+    /// Interprets a physical header, telling the type by its length.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the input is neither 5 nor 10 bytes long.
+    pub fn parse(data: &'buf [u8]) -> Result<Self, ParsingError> {
+        if let Ok(data) = data.try_into() {
+            return Ok(Self::Type1(Type1(data)));
+        }
+        if let Ok(data) = data.try_into() {
+            return Ok(Self::Type2(Type2(data)));
+        }
+        Err(ParsingError)
+    }
+
+    /// The serialized header.
+    #[must_use]
+    pub fn as_bytes(&self) -> &'buf [u8] {
+        match self {
+            Self::Type1(Type1(data)) => &data[..],
+            Self::Type2(Type2(data)) => &data[..],
+        }
+    }
+
+    /// See [`Type1::header_format()`].
+    #[must_use]
+    pub fn header_format(&self) -> u8 {
+        match self {
+            Self::Type1(h) => h.header_format(),
+            Self::Type2(h) => h.header_format(),
+        }
+    }
+
+    /// See [`Type1::packet_length_type()`].
+    #[must_use]
+    pub fn packet_length_type(&self) -> PacketLengthType {
+        match self {
+            Self::Type1(h) => h.packet_length_type(),
+            Self::Type2(h) => h.packet_length_type(),
+        }
+    }
+
+    /// See [`Type1::packet_length()`].
+    #[must_use]
+    pub fn packet_length(&self) -> u8 {
+        match self {
+            Self::Type1(h) => h.packet_length(),
+            Self::Type2(h) => h.packet_length(),
+        }
+    }
+
+    /// See [`Type1::short_network_id()`].
+    #[must_use]
+    pub fn short_network_id(&self) -> u8 {
+        match self {
+            Self::Type1(h) => h.short_network_id(),
+            Self::Type2(h) => h.short_network_id(),
+        }
+    }
+
+    /// See [`Type1::transmitter_identity()`].
+    #[must_use]
+    pub fn transmitter_identity(&self) -> u16 {
+        match self {
+            Self::Type1(h) => h.transmitter_identity(),
+            Self::Type2(h) => h.transmitter_identity(),
+        }
+    }
+
+    /// See [`Type1::transmit_power()`].
+    #[must_use]
+    pub fn transmit_power(&self) -> u8 {
+        match self {
+            Self::Type1(h) => h.transmit_power(),
+            Self::Type2(h) => h.transmit_power(),
+        }
+    }
+
+    /// The DF MCS, which is 3 bits wide in a Type 1 and 4 bits wide in a Type 2 header.
+    #[must_use]
+    pub fn df_mcs(&self) -> u8 {
+        match self {
+            Self::Type1(h) => h.df_mcs(),
+            Self::Type2(h) => h.df_mcs(),
+        }
+    }
+
+    /// The receiver identity, which is only present in a Type 2 header.
+    #[must_use]
+    pub fn receiver_identity(&self) -> Option<u16> {
+        match self {
+            Self::Type1(_) => None,
+            Self::Type2(h) => Some(h.receiver_identity()),
+        }
+    }
+}
+
+/// Builder for a [`Type1`] header.
+///
+/// The builder starts out with the shortest packet length, lowest transmit power and MCS 0; all
+/// setters validate that their input fits the field.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Type1Builder {
+    data: [u8; 5],
+}
+
+/// Builder for a [`Type2`] header.
+///
+/// Like [`Type1Builder`], but additionally carrying the receiver and feedback information. The
+/// header format starts out as 000 (with HARQ fields), with all HARQ fields and the feedback
+/// format set to zero.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Type2Builder {
+    data: [u8; 10],
+}
+
+/// Setters shared by the builders for their first 4 bytes.
+macro_rules! common_setters {
+    () => {
+        /// Sets the packet length in `units` of `type_`.
+        ///
+        /// # Errors
+        ///
+        /// This errs if `length` is not in the range 1 to 16.
+        pub fn packet_length(
+            mut self,
+            type_: PacketLengthType,
+            length: u8,
+        ) -> Result<Self, ValueRangeError> {
+            if !(1..=16).contains(&length) {
+                return Err(ValueRangeError);
+            }
+            self.data[0] = (self.data[0] & 0xe0) | (type_.bit() << 4) | (length - 1);
+            Ok(self)
+        }
+
+        /// Sets the Transmit Power field; see [`transmit_power_dbm()`] for its meaning.
+        ///
+        /// # Errors
+        ///
+        /// This errs if `code` exceeds 4 bits.
+        pub fn transmit_power(mut self, code: u8) -> Result<Self, ValueRangeError> {
+            if code > 0x0f {
+                return Err(ValueRangeError);
+            }
+            self.data[4] = (self.data[4] & 0x0f) | (code << 4);
+            Ok(self)
+        }
+
+        /// Serializes the header into any [`embedded_io::Write`]r.
+        ///
+        /// # Errors
+        ///
+        /// This merely forwards any errors of the writer.
+        pub fn serialize<W: embedded_io::Write>(&self, w: &mut W) -> Result<(), W::Error> {
+            w.write_all(&self.data)
+        }
+    };
+}
+
+impl Type1Builder {
+    /// Creates a header from the two identifiers that are always needed.
+    #[must_use]
+    pub fn new(short_network_id: u8, transmitter_identity: u16) -> Self {
+        let [tx_high, tx_low] = transmitter_identity.to_be_bytes();
+        Self {
+            data: [0, short_network_id, tx_high, tx_low, 0],
+        }
+    }
+
+    common_setters!();
+
+    /// Sets the DF MCS.
+    ///
+    /// # Errors
+    ///
+    /// This errs if `mcs` exceeds 3 bits.
+    pub fn df_mcs(mut self, mcs: u8) -> Result<Self, ValueRangeError> {
+        if mcs > 0x07 {
+            return Err(ValueRangeError);
+        }
+        self.data[4] = (self.data[4] & 0xf0) | mcs;
+        Ok(self)
+    }
+
+    /// The serialized header, suitable for passing on to a PHY.
+    #[must_use]
+    pub fn build(&self) -> [u8; 5] {
+        self.data
+    }
+}
+
+impl Type2Builder {
+    /// Creates a header from the identifiers that are always needed.
+    #[must_use]
+    pub fn new(short_network_id: u8, transmitter_identity: u16, receiver_identity: u16) -> Self {
+        let [tx_high, tx_low] = transmitter_identity.to_be_bytes();
+        let [rx_high, rx_low] = receiver_identity.to_be_bytes();
+        Self {
+            data: [
+                0,
+                short_network_id,
+                tx_high,
+                tx_low,
+                0,
+                rx_high,
+                rx_low,
+                0,
+                0,
+                0,
+            ],
+        }
+    }
+
+    common_setters!();
+
+    /// Sets the DF MCS.
+    ///
+    /// # Errors
+    ///
+    /// This errs if `mcs` exceeds 4 bits.
+    pub fn df_mcs(mut self, mcs: u8) -> Result<Self, ValueRangeError> {
+        if mcs > 0x0f {
+            return Err(ValueRangeError);
+        }
+        self.data[4] = (self.data[4] & 0xf0) | mcs;
+        Ok(self)
+    }
+
+    /// Sets the coded Number of Spatial Streams field.
+    ///
+    /// # Errors
+    ///
+    /// This errs if `coded` exceeds 2 bits.
+    pub fn number_of_spatial_streams(mut self, coded: u8) -> Result<Self, ValueRangeError> {
+        if coded > 0x03 {
+            return Err(ValueRangeError);
+        }
+        self.data[7] = (self.data[7] & 0x3f) | (coded << 6);
+        Ok(self)
+    }
+
+    /// Selects header format 000, and sets its HARQ fields.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the redundancy version exceeds 2 bits or the process number exceeds 3 bits.
+    pub fn harq(
+        mut self,
+        redundancy_version: u8,
+        new_data_indication: bool,
+        process_number: u8,
+    ) -> Result<Self, ValueRangeError> {
+        if redundancy_version > 0x03 || process_number > 0x07 {
+            return Err(ValueRangeError);
+        }
+        self.data[0] &= 0x1f;
+        self.data[7] = (self.data[7] & 0xc0)
+            | (redundancy_version << 4)
+            | (u8::from(new_data_indication) << 3)
+            | process_number;
+        Ok(self)
+    }
+
+    /// Selects header format 001, which carries no HARQ fields.
+    #[must_use]
+    pub fn without_harq(mut self) -> Self {
+        self.data[0] = (self.data[0] & 0x1f) | (0b001 << 5);
+        self.data[7] &= 0xc0;
+        self
+    }
+
+    /// Sets the Feedback Format and Feedback Info fields.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the format exceeds 4 bits or the info exceeds 12 bits.
+    pub fn feedback(mut self, format: u8, info: u16) -> Result<Self, ValueRangeError> {
+        if format > 0x0f || info > 0x0fff {
+            return Err(ValueRangeError);
+        }
+        let [info_high, info_low] = info.to_be_bytes();
+        self.data[8] = (format << 4) | info_high;
+        self.data[9] = info_low;
+        Ok(self)
+    }
+
+    /// The serialized header, suitable for passing on to a PHY.
+    #[must_use]
+    pub fn build(&self) -> [u8; 10] {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_type1() {
+        // Beacon header as seen from the dect_shell, also used in the tx example
+        let data = [17, 120, 150, 24, 112];
+        let Pcc::Type1(header) = Pcc::parse(&data).unwrap() else {
+            panic!("5 byte header was not recognized as Type 1");
+        };
+        assert_eq!(header.header_format(), 0);
+        assert_eq!(header.packet_length_type(), PacketLengthType::Slots);
+        assert_eq!(header.packet_length(), 2);
+        assert_eq!(header.short_network_id(), 0x78);
+        assert_eq!(header.transmitter_identity(), 0x9618);
+        assert_eq!(header.transmit_power(), 7);
+        assert_eq!(transmit_power_dbm(header.transmit_power()), 0);
+        assert_eq!(header.df_mcs(), 0);
+
+        let built = Type1Builder::new(0x78, 0x9618)
+            .packet_length(PacketLengthType::Slots, 2)
+            .unwrap()
+            .transmit_power(7)
+            .unwrap()
+            .build();
+        assert_eq!(built, data);
+
+        let mut written = [0; 5];
+        Type1Builder::new(0x78, 0x9618)
+            .packet_length(PacketLengthType::Slots, 2)
+            .unwrap()
+            .transmit_power(7)
+            .unwrap()
+            .serialize(&mut &mut written[..])
+            .unwrap();
+        assert_eq!(written, data);
+
+        Type1Builder::new(0, 0)
+            .packet_length(PacketLengthType::Subslots, 0)
+            .unwrap_err();
+        Type1Builder::new(0, 0)
+            .packet_length(PacketLengthType::Subslots, 17)
+            .unwrap_err();
+        Type1Builder::new(0, 0).df_mcs(8).unwrap_err();
+    }
+
+    #[test]
+    fn test_type2() {
+        let built = Type2Builder::new(0x41, 0x1234, 0x5678)
+            .packet_length(PacketLengthType::Subslots, 3)
+            .unwrap()
+            .transmit_power(11)
+            .unwrap()
+            .df_mcs(4)
+            .unwrap()
+            .harq(2, true, 5)
+            .unwrap()
+            .feedback(1, 0xabc)
+            .unwrap()
+            .build();
+        assert_eq!(
+            built,
+            [0x02, 0x41, 0x12, 0x34, 0xb4, 0x56, 0x78, 0x2d, 0x1a, 0xbc]
+        );
+
+        let Pcc::Type2(header) = Pcc::parse(&built).unwrap() else {
+            panic!("10 byte header was not recognized as Type 2");
+        };
+        assert_eq!(header.header_format(), 0);
+        assert_eq!(header.packet_length_type(), PacketLengthType::Subslots);
+        assert_eq!(header.packet_length(), 3);
+        assert_eq!(header.transmitter_identity(), 0x1234);
+        assert_eq!(header.receiver_identity(), 0x5678);
+        assert_eq!(header.df_mcs(), 4);
+        assert_eq!(header.df_redundancy_version(), Some(2));
+        assert_eq!(header.df_new_data_indication(), Some(true));
+        assert_eq!(header.df_harq_process_number(), Some(5));
+        assert_eq!(header.feedback_format(), 1);
+        assert_eq!(header.feedback_info(), 0xabc);
+
+        let built = Type2Builder::new(0x41, 0x1234, 0x5678)
+            .harq(2, true, 5)
+            .unwrap()
+            .without_harq()
+            .build();
+        let Pcc::Type2(header) = Pcc::parse(&built).unwrap() else {
+            panic!("10 byte header was not recognized as Type 2");
+        };
+        assert_eq!(header.header_format(), 1);
+        assert_eq!(header.df_harq_process_number(), None);
+
+        Pcc::parse(&built[..7]).unwrap_err();
+    }
+}
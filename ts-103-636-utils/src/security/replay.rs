@@ -0,0 +1,323 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Packet counters and replay protection
+//!
+//! Every secured PDU is identified by its 12-bit Packet Sequence Number (PSN, the Sequence Number
+//! of the common header) and the 32-bit Hyper Packet Counter (HPC), which the sender increments
+//! whenever the PSN wraps around. The HPC is only sent in a MAC Security Info IE; otherwise the
+//! receiver infers it from the packets it saw before.
+//!
+//! A [`TxContext`] allocates those counters for outgoing PDUs to a peer, and an [`RxContext`]
+//! tracks what was received from a peer, rejecting replays through a sliding window. Both are
+//! pure logic, and work on the 44-bit combination of HPC and PSN.
+
+use crate::mac_pdu::Header;
+
+/// Number of counter values below the highest one received for which reception is still tracked.
+pub const WINDOW: u64 = 64;
+
+/// Number of values of the PSN.
+const PSN_MODULUS: u64 = 1 << 12;
+
+fn combine(hpc: u32, psn: u16) -> u64 {
+    (u64::from(hpc) << 12) | (u64::from(psn) & (PSN_MODULUS - 1))
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "PSN is masked to 12 bits, and the HPC range was checked at construction"
+)]
+fn split(counter: u64) -> (u32, u16) {
+    ((counter >> 12) as u32, (counter & (PSN_MODULUS - 1)) as u16)
+}
+
+/// Counter values for one outgoing PDU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TxCounter {
+    /// HPC for the IV (and the MAC Security Info IE, if sent).
+    pub hpc: u32,
+    /// PSN to send as the Sequence Number of the common header.
+    pub psn: u16,
+    /// Whether the PDU should carry a MAC Security Info IE, announcing the HPC to the peer.
+    ///
+    /// This is set for the first PDU, after the HPC was incremented, and after
+    /// [`TxContext::request_announcement()`].
+    pub announce_hpc: bool,
+}
+
+/// Allocator of the counters of PDUs sent to a peer.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TxContext {
+    next: u64,
+    announce: bool,
+}
+
+impl TxContext {
+    /// Starts sending at the given HPC with PSN 0.
+    #[must_use]
+    pub fn new(hpc: u32) -> Self {
+        Self {
+            next: combine(hpc, 0),
+            announce: true,
+        }
+    }
+
+    /// Makes the next PDU announce the HPC.
+    ///
+    /// This is used when the peer asked for it (see
+    /// [`SecurityIvType::OneTimeHpcWithHpcRequest`][crate::mac_ie::security_info::SecurityIvType::OneTimeHpcWithHpcRequest]).
+    pub fn request_announcement(&mut self) {
+        self.announce = true;
+    }
+
+    /// Allocates the counters for the next PDU.
+    ///
+    /// # Errors
+    ///
+    /// This errs once the HPC is exhausted; the keys then need to be renewed.
+    pub fn allocate(&mut self) -> Result<TxCounter, CounterExhausted> {
+        if self.next >> 12 > u64::from(u32::MAX) {
+            return Err(CounterExhausted);
+        }
+        let (hpc, psn) = split(self.next);
+        let counter = TxCounter {
+            hpc,
+            psn,
+            announce_hpc: self.announce,
+        };
+        self.next += 1;
+        self.announce = self.next.is_multiple_of(PSN_MODULUS);
+        Ok(counter)
+    }
+}
+
+/// All counter values of a [`TxContext`] have been used up.
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CounterExhausted;
+
+/// Counter values of a received PDU that passed the replay check.
+///
+/// This is produced by [`RxContext::check()`], and given back to [`RxContext::accept()`] once the
+/// PDU's MIC was verified.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxCounter(u64);
+
+impl RxCounter {
+    /// The HPC to build the IV with.
+    #[must_use]
+    pub fn hpc(self) -> u32 {
+        split(self.0).0
+    }
+
+    /// The PSN of the PDU.
+    #[must_use]
+    pub fn psn(self) -> u16 {
+        split(self.0).1
+    }
+}
+
+/// Reasons why [`RxContext::check()`] rejects a PDU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReplayError {
+    /// Nothing was received from the peer yet, and the PDU does not announce its HPC.
+    UnknownHpc,
+    /// The PDU has no sequence number (as beacons do not), so it can not be checked.
+    Unsequenced,
+    /// A PDU with the same counter values was already accepted.
+    Replayed,
+    /// The PDU is older than what the window tracks.
+    TooOld,
+}
+
+/// Replay protection state for PDUs received from a peer.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxContext {
+    /// Highest counter accepted so far.
+    highest: Option<u64>,
+    /// Bit `n` is set if `highest - n` was accepted.
+    window: u64,
+}
+
+impl RxContext {
+    /// Creates a context in which nothing was received yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Determines the counter values of a received PDU, and checks that it is no replay.
+    ///
+    /// If the PDU carries a MAC Security Info IE, its HPC is used, which resynchronizes the
+    /// context. Otherwise, the HPC is inferred as the one that puts the PSN closest to the highest
+    /// PSN received so far, which tolerates the sender's PSN wrapping around.
+    ///
+    /// This does not alter the context: The PDU's MIC is to be verified first with the resulting
+    /// [`RxCounter::hpc()`], and only then is the counter to be passed to [`Self::accept()`].
+    ///
+    /// # Errors
+    ///
+    /// This errs if the HPC is unknown, or if the counter was already accepted or is too old to
+    /// tell.
+    pub fn check(&self, psn: u16, announced_hpc: Option<u32>) -> Result<RxCounter, ReplayError> {
+        let counter = match (announced_hpc, self.highest) {
+            (Some(hpc), _) => combine(hpc, psn),
+            (None, Some(highest)) => infer(highest, psn).ok_or(ReplayError::TooOld)?,
+            (None, None) => return Err(ReplayError::UnknownHpc),
+        };
+        if let Some(highest) = self.highest
+            && counter <= highest
+        {
+            let age = highest - counter;
+            if age >= WINDOW {
+                return Err(ReplayError::TooOld);
+            }
+            if self.window & (1 << age) != 0 {
+                return Err(ReplayError::Replayed);
+            }
+        }
+        Ok(RxCounter(counter))
+    }
+
+    /// Runs [`Self::check()`] on the sequence number and MAC Security Info IE of a parsed PDU.
+    ///
+    /// # Errors
+    ///
+    /// This errs like [`Self::check()`], and on PDUs without a sequence number.
+    pub fn check_header(&self, header: &Header<'_>) -> Result<RxCounter, ReplayError> {
+        let psn = header.sequence_bytes().ok_or(ReplayError::Unsequenced)?;
+        let announced_hpc = header
+            .security
+            .as_ref()
+            .and_then(|security| security.security_info.as_ref())
+            .map(crate::mac_ie::security_info::MacSecurityInfo::hpc);
+        self.check(u16::from_be_bytes(psn), announced_hpc)
+    }
+
+    /// Records that a PDU with the counter values was received and verified.
+    pub fn accept(&mut self, counter: RxCounter) {
+        let counter = counter.0;
+        match self.highest {
+            Some(highest) if counter <= highest => {
+                let age = highest - counter;
+                if age < WINDOW {
+                    self.window |= 1 << age;
+                }
+            }
+            Some(highest) => {
+                let shift = counter - highest;
+                self.window = if shift < WINDOW {
+                    (self.window << shift) | 1
+                } else {
+                    1
+                };
+                self.highest = Some(counter);
+            }
+            None => {
+                self.window = 1;
+                self.highest = Some(counter);
+            }
+        }
+    }
+}
+
+/// Picks the counter with the given PSN that is closest to `highest`.
+///
+/// Returns `None` if that would require an HPC outside its range.
+fn infer(highest: u64, psn: u16) -> Option<u64> {
+    let psn = u64::from(psn) & (PSN_MODULUS - 1);
+    let highest_psn = highest % PSN_MODULUS;
+    let hpc = highest / PSN_MODULUS;
+    let hpc = if psn + PSN_MODULUS / 2 < highest_psn {
+        hpc + 1
+    } else if highest_psn + PSN_MODULUS / 2 < psn {
+        hpc.checked_sub(1)?
+    } else {
+        hpc
+    };
+    u32::try_from(hpc)
+        .is_ok()
+        .then_some(hpc * PSN_MODULUS + psn)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tx() {
+        let mut tx = TxContext::new(7);
+        let first = tx.allocate().unwrap();
+        assert_eq!(
+            first,
+            TxCounter {
+                hpc: 7,
+                psn: 0,
+                announce_hpc: true
+            }
+        );
+        assert!(!tx.allocate().unwrap().announce_hpc);
+        for _ in 2..0xfff {
+            tx.allocate().unwrap();
+        }
+        let last = tx.allocate().unwrap();
+        assert_eq!((last.hpc, last.psn, last.announce_hpc), (7, 0xfff, false));
+        let wrapped = tx.allocate().unwrap();
+        assert_eq!(
+            (wrapped.hpc, wrapped.psn, wrapped.announce_hpc),
+            (8, 0, true)
+        );
+        tx.request_announcement();
+        assert!(tx.allocate().unwrap().announce_hpc);
+
+        let mut tx = TxContext::new(u32::MAX);
+        for _ in 0..=0xfff {
+            tx.allocate().unwrap();
+        }
+        tx.allocate().unwrap_err();
+    }
+
+    #[test]
+    fn test_rx() {
+        let mut rx = RxContext::new();
+        assert_eq!(rx.check(5, None), Err(ReplayError::UnknownHpc));
+
+        let counter = rx.check(5, Some(3)).unwrap();
+        assert_eq!((counter.hpc(), counter.psn()), (3, 5));
+        // Not accepted yet, e.g. because the MIC did not match
+        assert_eq!(rx.check(5, Some(3)), Ok(counter));
+        rx.accept(counter);
+        assert_eq!(rx.check(5, None), Err(ReplayError::Replayed));
+
+        // Out of order within the window
+        rx.accept(rx.check(10, None).unwrap());
+        rx.accept(rx.check(7, None).unwrap());
+        assert_eq!(rx.check(7, None), Err(ReplayError::Replayed));
+        rx.accept(rx.check(6, None).unwrap());
+
+        // Falling out of the window
+        rx.accept(rx.check(80, None).unwrap());
+        assert_eq!(rx.check(9, None), Err(ReplayError::TooOld));
+
+        // PSN wrapping around increments the inferred HPC
+        rx.accept(rx.check(0xffe, Some(3)).unwrap());
+        let counter = rx.check(1, None).unwrap();
+        assert_eq!((counter.hpc(), counter.psn()), (4, 1));
+        rx.accept(counter);
+        // ... and late packets from before the wrap are still recognized
+        let counter = rx.check(0xfff, None).unwrap();
+        assert_eq!(counter.hpc(), 3);
+        rx.accept(counter);
+        assert_eq!(rx.check(0xffe, None), Err(ReplayError::Replayed));
+
+        // Resynchronization to a much higher HPC, after which old HPCs are rejected
+        rx.accept(rx.check(0, Some(1000)).unwrap());
+        assert_eq!(rx.check(2, Some(4)), Err(ReplayError::TooOld));
+        assert_eq!(rx.check(1, None).unwrap().hpc(), 1000);
+    }
+}
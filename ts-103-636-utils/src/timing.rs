@@ -0,0 +1,97 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Frame structure timing
+//!
+//! Durations are expressed in ticks of a 69.120 MHz clock, which is the unit in which DECT NR+
+//! modems (in particular the nRF91 series) express time. The frame structure follows
+//! ETSI TS 103 636-3 V2.1.1: A frame is 10 ms long and consists of 24 slots, each of which is
+//! divided into 2, 4, 8 or 16 subslots depending on the subcarrier scaling factor µ.
+
+/// Ticks per second.
+pub const TICKS_PER_SECOND: u64 = 69_120_000;
+
+/// Duration of a frame (10 ms).
+pub const FRAME: u64 = 691_200;
+
+/// Duration of a slot (1/24 of a frame).
+pub const SLOT: u64 = FRAME / 24;
+
+/// Duration of a subslot at subcarrier scaling factor `mu` (1, 2, 4 or 8).
+///
+/// # Panics
+///
+/// This panics if `mu` is 0.
+#[must_use]
+pub const fn subslot(mu: u8) -> u64 {
+    SLOT / (2 * mu as u64)
+}
+
+/// A frame at a known point in time, against which frame-relative scheduling information (e.g.
+/// from a Random Access Resource IE) is interpreted.
+///
+/// Typically, this is derived from a received cluster beacon: It is sent at the start of its frame
+/// (unless a frame offset is announced), and carries the System Frame Number.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FrameReference {
+    /// Time at which the frame started.
+    pub start: u64,
+    /// The System Frame Number (SFN) of that frame.
+    pub system_frame_number: u8,
+}
+
+impl FrameReference {
+    /// Start time of the first frame at or after this one that has the given SFN.
+    #[must_use]
+    pub fn start_of_frame_number(&self, system_frame_number: u8) -> u64 {
+        let frames_ahead = system_frame_number.wrapping_sub(self.system_frame_number);
+        self.start + u64::from(frames_ahead) * FRAME
+    }
+}
+
+/// Schedule of a resource that is announced relative to a frame, and may be repeated at a fixed
+/// interval during a number of frames.
+///
+/// This is shared by the MAC IEs that describe resources with a Repeat, Repetition and Validity
+/// field.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Repetitions {
+    first_frame: u64,
+    step: Option<u64>,
+    end: u64,
+}
+
+impl Repetitions {
+    /// Describes repetitions every `step` ticks (or a single occurrence if `None`), starting at the
+    /// frame that starts at `first_frame`, and valid for `validity_frames` frames (or indefinitely
+    /// if `None`).
+    pub(crate) fn new(first_frame: u64, step: Option<u64>, validity_frames: Option<u8>) -> Self {
+        let end = match (step, validity_frames) {
+            (Some(_), Some(frames)) => first_frame + u64::from(frames) * FRAME,
+            _ => u64::MAX,
+        };
+        Self {
+            first_frame,
+            step,
+            end,
+        }
+    }
+
+    /// Start times of the repetitions (i.e. the first frame and the offsets of the repetitions
+    /// from there).
+    pub(crate) fn bases(self) -> impl Iterator<Item = u64> {
+        let step = self.step.unwrap_or(u64::MAX);
+        let count = if self.step.is_some() { u64::MAX } else { 1 };
+        (0..count).map_while(move |n| {
+            let base = step.checked_mul(n)?.checked_add(self.first_frame)?;
+            (base < self.end).then_some(base)
+        })
+    }
+
+    /// Start times of a transmission `offset` ticks into the resource in each repetition.
+    pub(crate) fn starts(self, offset: u64) -> impl Iterator<Item = u64> {
+        self.bases()
+            .map_while(move |base| base.checked_add(offset))
+            .take_while(move |start| *start < self.end)
+    }
+}
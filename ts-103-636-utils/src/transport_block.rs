@@ -0,0 +1,330 @@
+// SPDX-FileCopyrightText: Copyright Christian Amsüss <chrysn@fsfe.org>, Silano Systems
+// SPDX-License-Identifier: MIT OR Apache-2.0
+//! Transport block size (TBS) of the PDC, and packet airtime
+//!
+//! The PDC of a packet carries exactly one transport block, whose size follows from the packet
+//! length and the physical layer parameters as described in Section 5.3 of ETSI TS 103 636-3
+//! V2.1.1. A MAC PDU sent in the packet needs to be padded up to that size (see
+//! [`MacPduBuilder`][crate::mac_pdu::builder::MacPduBuilder]).
+//!
+//! The calculation assumes that the number of effective transmit antennas equals the number of
+//! spatial streams, i.e. that no transmit diversity is used.
+
+use crate::ValueRangeError;
+use crate::pcc::{PacketLengthType, Pcc};
+use crate::timing;
+
+/// Length of the CRCs of the transport block and of the code blocks.
+const CRC_LEN: u32 = 24;
+
+/// Number of subcarriers occupied by the PCC.
+const PCC_SUBCARRIERS: u32 = 98;
+
+/// Number of OFDM symbols in a subslot (independent of µ).
+const SUBSLOT_SYMBOLS: u32 = 5;
+
+/// Bits per modulation symbol, and code rate numerator and denominator of the MCS values of
+/// Table A-1 of ETSI TS 103 636-3 V2.1.1.
+const MCS_TABLE: [(u32, u32, u32); 12] = [
+    (1, 1, 2),
+    (2, 1, 2),
+    (2, 3, 4),
+    (4, 1, 2),
+    (4, 3, 4),
+    (6, 2, 3),
+    (6, 3, 4),
+    (6, 5, 6),
+    (8, 3, 4),
+    (8, 5, 6),
+    (10, 3, 4),
+    (10, 5, 6),
+];
+
+/// Maximum code block size Z, which is part of a radio device's capabilities.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CodeBlockSize {
+    Z2048,
+    Z6144,
+}
+
+impl CodeBlockSize {
+    fn bits(self) -> u32 {
+        match self {
+            Self::Z2048 => 2048,
+            Self::Z6144 => 6144,
+        }
+    }
+}
+
+/// Length of a packet as expressed in the PCC.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PacketLength {
+    type_: PacketLengthType,
+    length: u8,
+}
+
+impl PacketLength {
+    /// Creates a packet length of `length` units of `type_`.
+    ///
+    /// # Errors
+    ///
+    /// This errs if `length` is not in the range 1 to 16.
+    pub fn new(type_: PacketLengthType, length: u8) -> Result<Self, ValueRangeError> {
+        if !(1..=16).contains(&length) {
+            return Err(ValueRangeError);
+        }
+        Ok(Self { type_, length })
+    }
+
+    /// Reads the packet length announced in a physical header.
+    #[must_use]
+    pub fn from_pcc(pcc: &Pcc<'_>) -> Self {
+        Self {
+            type_: pcc.packet_length_type(),
+            length: pcc.packet_length(),
+        }
+    }
+
+    /// The unit of [`Self::length()`].
+    #[must_use]
+    pub fn type_(&self) -> PacketLengthType {
+        self.type_
+    }
+
+    /// The length in units of [`Self::type_()`], in the range 1 to 16.
+    #[must_use]
+    pub fn length(&self) -> u8 {
+        self.length
+    }
+
+    /// Number of OFDM symbols in the packet at subcarrier scaling factor `mu`.
+    fn symbols(self, mu: u8) -> u32 {
+        let subslots = match self.type_ {
+            PacketLengthType::Subslots => u32::from(self.length),
+            PacketLengthType::Slots => u32::from(self.length) * 2 * u32::from(mu),
+        };
+        subslots * SUBSLOT_SYMBOLS
+    }
+
+    /// Duration of the packet (including its guard interval) at subcarrier scaling factor `mu`,
+    /// in [ticks][timing::TICKS_PER_SECOND].
+    ///
+    /// # Panics
+    ///
+    /// This panics if `mu` is 0.
+    #[must_use]
+    pub fn airtime(&self, mu: u8) -> u64 {
+        let unit = match self.type_ {
+            PacketLengthType::Subslots => timing::subslot(mu),
+            PacketLengthType::Slots => timing::SLOT,
+        };
+        u64::from(self.length) * unit
+    }
+}
+
+/// Physical layer parameters that, together with the [`PacketLength`], determine the size of the
+/// transport block.
+///
+/// This starts out with µ = 1, β = 1, a single spatial stream and Z = 2048, which is what all
+/// devices support.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PdcFormat {
+    mu: u8,
+    beta: u8,
+    mcs: u8,
+    spatial_streams: u8,
+    code_block_size: CodeBlockSize,
+}
+
+impl PdcFormat {
+    /// Creates a format with the given DF MCS.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the MCS exceeds 11.
+    pub fn new(mcs: u8) -> Result<Self, ValueRangeError> {
+        if usize::from(mcs) >= MCS_TABLE.len() {
+            return Err(ValueRangeError);
+        }
+        Ok(Self {
+            mu: 1,
+            beta: 1,
+            mcs,
+            spatial_streams: 1,
+            code_block_size: CodeBlockSize::Z2048,
+        })
+    }
+
+    /// Reads the DF MCS and number of spatial streams from a physical header.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the MCS has a reserved value.
+    pub fn from_pcc(pcc: &Pcc<'_>) -> Result<Self, ValueRangeError> {
+        let format = Self::new(pcc.df_mcs())?;
+        Ok(match pcc {
+            Pcc::Type1(_) => format,
+            Pcc::Type2(header) => Self {
+                spatial_streams: 1 << header.number_of_spatial_streams(),
+                ..format
+            },
+        })
+    }
+
+    /// Sets the subcarrier scaling factor µ and the Fourier transform scaling factor β.
+    ///
+    /// # Errors
+    ///
+    /// This errs if µ is not 1, 2, 4 or 8, or if β is not 1, 2, 4, 8, 12 or 16.
+    pub fn numerology(mut self, mu: u8, beta: u8) -> Result<Self, ValueRangeError> {
+        if !matches!(mu, 1 | 2 | 4 | 8) || !matches!(beta, 1 | 2 | 4 | 8 | 12 | 16) {
+            return Err(ValueRangeError);
+        }
+        self.mu = mu;
+        self.beta = beta;
+        Ok(self)
+    }
+
+    /// Sets the number of spatial streams.
+    ///
+    /// # Errors
+    ///
+    /// This errs if the number is not 1, 2, 4 or 8.
+    pub fn spatial_streams(mut self, spatial_streams: u8) -> Result<Self, ValueRangeError> {
+        if !matches!(spatial_streams, 1 | 2 | 4 | 8) {
+            return Err(ValueRangeError);
+        }
+        self.spatial_streams = spatial_streams;
+        Ok(self)
+    }
+
+    /// Sets the maximum code block size of the receiver.
+    #[must_use]
+    pub fn code_block_size(mut self, code_block_size: CodeBlockSize) -> Self {
+        self.code_block_size = code_block_size;
+        self
+    }
+
+    /// Number of subcarriers (resource elements) available to the PDC.
+    fn pdc_subcarriers(self, packet_length: PacketLength) -> u32 {
+        let symbols = packet_length.symbols(self.mu);
+        let occupied = 56 * u32::from(self.beta);
+        let guard_and_stf = if self.mu <= 2 { 2 } else { 3 };
+        let drs_step = if self.spatial_streams <= 2 { 5 } else { 10 };
+        let drs = u32::from(self.spatial_streams) * (occupied / 4) * (symbols / drs_step);
+        ((symbols - guard_and_stf) * occupied).saturating_sub(drs + PCC_SUBCARRIERS)
+    }
+
+    /// Size of the transport block in bits.
+    #[must_use]
+    pub fn transport_block_bits(&self, packet_length: PacketLength) -> u32 {
+        let (bits_per_symbol, rate_numerator, rate_denominator) = MCS_TABLE[usize::from(self.mcs)];
+        let pdc_bits = self.pdc_subcarriers(packet_length)
+            * u32::from(self.spatial_streams)
+            * bits_per_symbol
+            * rate_numerator
+            / rate_denominator;
+        let granularity = if pdc_bits <= 512 {
+            8
+        } else {
+            1 << (pdc_bits.ilog2() - 5)
+        };
+        let n = pdc_bits / granularity * granularity;
+        let z = self.code_block_size.bits();
+        if n <= z {
+            n.saturating_sub(CRC_LEN)
+        } else {
+            let code_blocks = (n - CRC_LEN).div_ceil(z - CRC_LEN);
+            n - (code_blocks + 1) * CRC_LEN
+        }
+    }
+
+    /// Size of the transport block in bytes, i.e. the length of the MAC PDU sent in the packet.
+    #[must_use]
+    pub fn transport_block_size(&self, packet_length: PacketLength) -> usize {
+        (self.transport_block_bits(packet_length) / 8) as usize
+    }
+
+    /// The shortest packet whose transport block holds `len` bytes.
+    ///
+    /// Lengths in subslots are preferred when a length in slots takes the same time. Returns
+    /// `None` if even the longest packet is too short.
+    #[must_use]
+    pub fn min_packet_length(&self, len: usize) -> Option<PacketLength> {
+        let candidates = (1..=16)
+            .map(|length| PacketLength {
+                type_: PacketLengthType::Subslots,
+                length,
+            })
+            .chain((1..=16).map(|length| PacketLength {
+                type_: PacketLengthType::Slots,
+                length,
+            }));
+        candidates
+            .filter(|candidate| self.transport_block_size(*candidate) >= len)
+            .min_by_key(|candidate| candidate.symbols(self.mu))
+    }
+
+    /// Airtime of the shortest packet that holds `len` bytes, in
+    /// [ticks][timing::TICKS_PER_SECOND].
+    ///
+    /// Returns `None` if even the longest packet is too short.
+    #[must_use]
+    pub fn min_airtime(&self, len: usize) -> Option<u64> {
+        Some(self.min_packet_length(len)?.airtime(self.mu))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_transport_block_size() {
+        let subslots = |length| PacketLength::new(PacketLengthType::Subslots, length).unwrap();
+        let mcs0 = PdcFormat::new(0).unwrap();
+        // Sizes the ping example found to work with the nRF91 modem
+        assert_eq!(mcs0.transport_block_size(subslots(2)), 17);
+        assert_eq!(mcs0.transport_block_size(subslots(3)), 33);
+        assert_eq!(mcs0.transport_block_size(subslots(1)), 0);
+        assert_eq!(
+            PdcFormat::new(1).unwrap().transport_block_size(subslots(2)),
+            37
+        );
+
+        // Segmentation into code blocks
+        let long = PacketLength::new(PacketLengthType::Slots, 16).unwrap();
+        let mcs11 = PdcFormat::new(11).unwrap();
+        assert_eq!(mcs11.transport_block_bits(long), 66744);
+        assert_eq!(
+            mcs11
+                .code_block_size(CodeBlockSize::Z6144)
+                .transport_block_bits(long),
+            67584 - 13 * 24
+        );
+
+        PdcFormat::new(12).unwrap_err();
+        mcs0.numerology(3, 1).unwrap_err();
+        mcs0.spatial_streams(3).unwrap_err();
+    }
+
+    #[test]
+    fn test_min_packet_length() {
+        let mcs0 = PdcFormat::new(0).unwrap();
+        assert_eq!(
+            mcs0.min_packet_length(20),
+            Some(PacketLength::new(PacketLengthType::Subslots, 3).unwrap())
+        );
+        assert_eq!(mcs0.min_airtime(20), Some(3 * timing::subslot(1)));
+        assert_eq!(mcs0.min_packet_length(100_000), None);
+
+        // Longer than 16 subslots
+        assert_eq!(
+            mcs0.min_packet_length(300),
+            Some(PacketLength::new(PacketLengthType::Slots, 10).unwrap())
+        );
+    }
+}